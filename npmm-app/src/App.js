@@ -5,11 +5,18 @@ import "./App.css";
 function App() {
     const gameControllerRef = useRef(null);
     const [board, setBoard] = useState([]);
+    const [boardCells, setBoardCells] = useState([]);
+    const [gameInfo, setGameInfo] = useState(null);
     const [toMove, setToMove] = useState(0);
     const [moveNum, setMoveNum] = useState(0);
     const [status, setStatus] = useState(null);
     const [numPlayers, setNumPlayers] = useState(2);
+    const [games, setGames] = useState([]);
+    const [gameName, setGameName] = useState("user_game");
     const [availableMoves, setAvailableMoves] = useState([]);
+    const [canPass, setCanPass] = useState(false);
+    const [canUndo, setCanUndo] = useState(false);
+    const [canRedo, setCanRedo] = useState(false);
     const [wasmModule, setWasmModule] = useState(null);
     const [wasmArrayBuffer, setWasmArrayBuffer] = useState(null);
 
@@ -17,77 +24,181 @@ function App() {
     const updateGameState = useCallback(() => {
         console.log(gameControllerRef.current);
         setBoard(gameControllerRef.current.get_board());
+        setBoardCells(gameControllerRef.current.get_board_cells());
+        setGameInfo(gameControllerRef.current.get_game_info());
         setToMove(gameControllerRef.current.get_to_move());
         setMoveNum(gameControllerRef.current.get_move_num());
         setStatus(gameControllerRef.current.get_game_status());
         setAvailableMoves(gameControllerRef.current.get_valid_moves());
+        setCanPass(gameControllerRef.current.can_pass());
+        setCanUndo(gameControllerRef.current.can_undo());
+        setCanRedo(gameControllerRef.current.can_redo());
     }, []);
 
     const handleReset = useCallback(() => {
         if (wasmModule) {
-            gameControllerRef.current =
-                wasmModule.create_game_controller(numPlayers);
+            gameControllerRef.current = wasmModule.create_game_controller_for(
+                gameName,
+                numPlayers
+            );
             console.log(gameControllerRef.current);
             updateGameState();
         }
-    }, [numPlayers, updateGameState, wasmModule]);
+    }, [gameName, numPlayers, updateGameState, wasmModule]);
 
     const handlePlayerSelection = (players) => {
         setNumPlayers(players);
         handleReset();
     };
 
+    const selectedGame = games.find((game) => game.name === gameName);
+
+    // Keeps the number of players within what the new game supports
+    const handleGameSelection = (name) => {
+        const game = games.find((game) => game.name === name);
+        setGameName(name);
+        const { min_players, max_players } = game.metadata;
+        setNumPlayers(Math.min(Math.max(numPlayers, min_players), max_players));
+    };
+
     // Reset the game when the wasmModule loads (after game is created)
     useEffect(() => {
         if (wasmModule && wasmArrayBuffer) {
-            wasmModule.default(wasmArrayBuffer).then(handleReset);
+            wasmModule.default(wasmArrayBuffer).then(() => {
+                setGames(wasmModule.list_games());
+                handleReset();
+            });
         }
     }, [handleReset, wasmModule, wasmArrayBuffer]);
 
+    // The placement onto (row, col) among the valid moves, if there is one
+    const findPlacement = (row, col) =>
+        availableMoves.find(
+            (move) =>
+                move.Play &&
+                !move.Play.from &&
+                move.Play.to[0] === row &&
+                move.Play.to[1] === col
+        );
+
+    // Moves the game rejects are logged rather than played
+    const tryMove = useCallback(
+        (makeMove) => {
+            try {
+                makeMove(gameControllerRef.current);
+            } catch (err) {
+                console.error(err.message);
+            }
+            updateGameState();
+        },
+        [updateGameState]
+    );
+
     const handleClick = (row, col) => {
         console.log(row, col);
-        gameControllerRef.current.make_human_move(row, col);
-        updateGameState();
+        tryMove((controller) => controller.make_move(findPlacement(row, col)));
+    };
+
+    const handlePass = () => {
+        tryMove((controller) => controller.make_pass_move());
+    };
+
+    // Rewinds the AI moves too, so that it's the human's turn again
+    const handleUndo = () => {
+        tryMove((controller) => controller.undo_to_last_turn(0));
+    };
+
+    // Replays moves up to the human's next turn
+    const handleRedo = () => {
+        tryMove((controller) => {
+            do {
+                controller.redo();
+            } while (controller.can_redo() && controller.get_to_move() !== 0);
+        });
     };
 
     useEffect(() => {
         if (
             wasmModule &&
-            toMove !== wasmModule.Player.Player0 &&
-            status === wasmModule.GameStatus.Ongoing
+            toMove !== 0 &&
+            status === "Ongoing"
         ) {
             setTimeout(() => {
-                gameControllerRef.current.make_ai_move();
-                updateGameState();
+                tryMove((controller) => controller.make_ai_move());
             }, 25);
         }
-    }, [toMove, status, updateGameState, wasmModule]);
+    }, [toMove, status, tryMove, wasmModule]);
+
+    // The color of the player owning the top piece of a cell, if any
+    const getCellColor = (row, col) => {
+        const cell = boardCells[row] && boardCells[row][col];
+        const pieces = cell && (cell.Stack || (cell.Piece && [cell.Piece]));
+        const owner = pieces && pieces[pieces.length - 1].owner;
+        return owner === undefined ? undefined : gameInfo.players[owner].color;
+    };
 
     const getStatusText = (status) => {
-        if (!wasmModule) return "";
+        if (!wasmModule || !status) return "";
         switch (status) {
-            case wasmModule.GameStatus.Ongoing:
+            case "Ongoing":
                 return "Game Ongoing";
-            case wasmModule.GameStatus.Tie:
+            case "Tie":
                 return "It's a Tie!";
             default:
-                return `Player ${status} Wins!`;
+                return status.Win !== undefined
+                    ? `Player ${status.Win} Wins!`
+                    : getOutcomeText(status.Over);
+        }
+    };
+
+    // Outcomes hold one entry per player
+    const getOutcomeText = (outcome) => {
+        if (outcome.Placements) {
+            const winners = outcome.Placements.map((place, player) =>
+                place === 0 ? player : null
+            ).filter((player) => player !== null);
+            return winners.length > 1
+                ? `Players ${winners.join(", ")} share the win!`
+                : `Player ${winners[0]} Wins!`;
         }
+        const values = outcome.Points || outcome.Utilities;
+        return `Final scores: ${values
+            .map((value, player) => `Player ${player}: ${value}`)
+            .join(", ")}`;
     };
 
     return (
         <div className="App">
-            <div className="player-selection">
-                {[2, 3, 4].map((num) => (
+            <div className="game-selection">
+                {games.map((game) => (
                     <button
-                        key={num}
-                        onClick={() => handlePlayerSelection(num)}
-                        className={numPlayers === num ? "selected" : ""}
+                        key={game.name}
+                        onClick={() => handleGameSelection(game.name)}
+                        className={gameName === game.name ? "selected" : ""}
+                        title={game.metadata.rules}
                     >
-                        {num} Players
+                        {game.metadata.name}
                     </button>
                 ))}
             </div>
+            <div className="player-selection">
+                {[2, 3, 4, 5, 6, 7, 8]
+                    .filter(
+                        (num) =>
+                            !selectedGame ||
+                            (num >= selectedGame.metadata.min_players &&
+                                num <= selectedGame.metadata.max_players)
+                    )
+                    .map((num) => (
+                        <button
+                            key={num}
+                            onClick={() => handlePlayerSelection(num)}
+                            className={numPlayers === num ? "selected" : ""}
+                        >
+                            {num} Players
+                        </button>
+                    ))}
+            </div>
             <GameDescriptionBox
                 setWasmModule={setWasmModule}
                 setWasmArrayBuffer={setWasmArrayBuffer}
@@ -98,11 +209,9 @@ function App() {
                         <div className="row" key={rowIndex}>
                             {row.map((cell, cellIndex) => {
                                 // Check if this cell is a valid move
-                                const isValidMove = availableMoves.some(
-                                    (move) =>
-                                        move[0] === rowIndex &&
-                                        move[1] === cellIndex
-                                );
+                                const isValidMove =
+                                    findPlacement(rowIndex, cellIndex) !==
+                                    undefined;
 
                                 // Conditional className based on whether the cell is a valid move
                                 const cellClass = isValidMove
@@ -118,12 +227,16 @@ function App() {
                                         }
                                         disabled={
                                             wasmModule &&
-                                            (status !==
-                                                wasmModule.GameStatus.Ongoing ||
-                                                toMove !==
-                                                    wasmModule.Player.Player0 ||
+                                            (status !== "Ongoing" ||
+                                                toMove !== 0 ||
                                                 !isValidMove)
                                         }
+                                        style={{
+                                            color: getCellColor(
+                                                rowIndex,
+                                                cellIndex
+                                            ),
+                                        }}
                                     >
                                         {cell}
                                     </button>
@@ -134,15 +247,40 @@ function App() {
                 </div>
                 {wasmModule && (
                     <div className="info-panel">
+                        {gameInfo && (
+                            <>
+                                <h3>{gameInfo.name}</h3>
+                                <p>{gameInfo.rules}</p>
+                            </>
+                        )}
                         <p>
                             Next Move:{" "}
-                            {toMove === wasmModule.Player.Player0
-                                ? "You"
-                                : "AI"}
+                            {toMove === 0 ? "You" : "AI"}
                         </p>
                         <p>Total Moves: {moveNum}</p>
                         <p>{getStatusText(status)}</p>
-                        {status !== wasmModule.GameStatus.Ongoing && (
+                        {status === "Ongoing" && toMove === 0 && canPass && (
+                            <button onClick={handlePass}>Pass</button>
+                        )}
+                        <button
+                            onClick={handleUndo}
+                            disabled={
+                                !canUndo ||
+                                (status === "Ongoing" && toMove !== 0)
+                            }
+                        >
+                            Undo
+                        </button>
+                        <button
+                            onClick={handleRedo}
+                            disabled={
+                                !canRedo ||
+                                (status === "Ongoing" && toMove !== 0)
+                            }
+                        >
+                            Redo
+                        </button>
+                        {status !== "Ongoing" && (
                             <button
                                 className="reset-button"
                                 onClick={handleReset}
@@ -1,420 +0,0 @@
-/* eslint-disable */
-let wasm;
-
-const cachedTextDecoder =
-    typeof TextDecoder !== "undefined"
-        ? new TextDecoder("utf-8", { ignoreBOM: true, fatal: true })
-        : {
-              decode: () => {
-                  throw Error("TextDecoder not available");
-              },
-          };
-
-if (typeof TextDecoder !== "undefined") {
-    cachedTextDecoder.decode();
-}
-
-let cachedUint8Memory0 = null;
-
-function getUint8Memory0() {
-    if (cachedUint8Memory0 === null || cachedUint8Memory0.byteLength === 0) {
-        cachedUint8Memory0 = new Uint8Array(wasm.memory.buffer);
-    }
-    return cachedUint8Memory0;
-}
-
-function getStringFromWasm0(ptr, len) {
-    ptr = ptr >>> 0;
-    return cachedTextDecoder.decode(getUint8Memory0().subarray(ptr, ptr + len));
-}
-
-const heap = new Array(128).fill(undefined);
-
-heap.push(undefined, null, true, false);
-
-let heap_next = heap.length;
-
-function addHeapObject(obj) {
-    if (heap_next === heap.length) heap.push(heap.length + 1);
-    const idx = heap_next;
-    heap_next = heap[idx];
-
-    heap[idx] = obj;
-    return idx;
-}
-
-function getObject(idx) {
-    return heap[idx];
-}
-
-function dropObject(idx) {
-    if (idx < 132) return;
-    heap[idx] = heap_next;
-    heap_next = idx;
-}
-
-function takeObject(idx) {
-    const ret = getObject(idx);
-    dropObject(idx);
-    return ret;
-}
-/**
- * @param {number} num_players
- * @returns {GameController}
- */
-export function create_game_controller(num_players) {
-    const ret = wasm.create_game_controller(num_players);
-    return GameController.__wrap(ret);
-}
-
-function handleError(f, args) {
-    try {
-        return f.apply(this, args);
-    } catch (e) {
-        wasm.__wbindgen_exn_store(addHeapObject(e));
-    }
-}
-/**
- */
-export const Player = Object.freeze({
-    Player0: 0,
-    0: "Player0",
-    Player1: 1,
-    1: "Player1",
-    Player2: 2,
-    2: "Player2",
-    Player3: 3,
-    3: "Player3",
-});
-/**
- */
-export const GameStatus = Object.freeze({
-    Player0Win: 0,
-    0: "Player0Win",
-    Player1Win: 1,
-    1: "Player1Win",
-    Player2Win: 2,
-    2: "Player2Win",
-    Player3Win: 3,
-    3: "Player3Win",
-    Ongoing: 4,
-    4: "Ongoing",
-    Tie: 5,
-    5: "Tie",
-});
-/**
- */
-export class GameController {
-    static __wrap(ptr) {
-        ptr = ptr >>> 0;
-        const obj = Object.create(GameController.prototype);
-        obj.__wbg_ptr = ptr;
-
-        return obj;
-    }
-
-    __destroy_into_raw() {
-        const ptr = this.__wbg_ptr;
-        this.__wbg_ptr = 0;
-
-        return ptr;
-    }
-
-    free() {
-        const ptr = this.__destroy_into_raw();
-        wasm.__wbg_gamecontroller_free(ptr);
-    }
-    /**
-     * @returns {Array<any>}
-     */
-    get_board() {
-        const ret = wasm.gamecontroller_get_board(this.__wbg_ptr);
-        return takeObject(ret);
-    }
-    /**
-     * @returns {number}
-     */
-    get_to_move() {
-        const ret = wasm.gamecontroller_get_to_move(this.__wbg_ptr);
-        return ret >>> 0;
-    }
-    /**
-     * @returns {number}
-     */
-    get_move_num() {
-        const ret = wasm.gamecontroller_get_move_num(this.__wbg_ptr);
-        return ret >>> 0;
-    }
-    /**
-     * @returns {number}
-     */
-    get_game_status() {
-        const ret = wasm.gamecontroller_get_game_status(this.__wbg_ptr);
-        return ret >>> 0;
-    }
-    /**
-     */
-    make_ai_move() {
-        wasm.gamecontroller_make_ai_move(this.__wbg_ptr);
-    }
-    /**
-     * @param {number} move_row
-     * @param {number} move_col
-     */
-    make_human_move(move_row, move_col) {
-        wasm.gamecontroller_make_human_move(this.__wbg_ptr, move_row, move_col);
-    }
-    /**
-     * @returns {Array<any>}
-     */
-    get_valid_moves() {
-        const ret = wasm.gamecontroller_get_valid_moves(this.__wbg_ptr);
-        return takeObject(ret);
-    }
-}
-
-async function __wbg_load(module, imports) {
-    if (typeof Response === "function" && module instanceof Response) {
-        if (typeof WebAssembly.instantiateStreaming === "function") {
-            try {
-                return await WebAssembly.instantiateStreaming(module, imports);
-            } catch (e) {
-                if (module.headers.get("Content-Type") != "application/wasm") {
-                    console.warn(
-                        "`WebAssembly.instantiateStreaming` failed because your server does not serve wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n",
-                        e
-                    );
-                } else {
-                    throw e;
-                }
-            }
-        }
-
-        const bytes = await module.arrayBuffer();
-        return await WebAssembly.instantiate(bytes, imports);
-    } else {
-        const instance = await WebAssembly.instantiate(module, imports);
-
-        if (instance instanceof WebAssembly.Instance) {
-            return { instance, module };
-        } else {
-            return instance;
-        }
-    }
-}
-
-function __wbg_get_imports() {
-    const imports = {};
-    imports.wbg = {};
-    imports.wbg.__wbindgen_string_new = function (arg0, arg1) {
-        const ret = getStringFromWasm0(arg0, arg1);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbindgen_number_new = function (arg0) {
-        const ret = arg0;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbindgen_object_drop_ref = function (arg0) {
-        takeObject(arg0);
-    };
-    imports.wbg.__wbg_crypto_c48a774b022d20ac = function (arg0) {
-        const ret = getObject(arg0).crypto;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbindgen_is_object = function (arg0) {
-        const val = getObject(arg0);
-        const ret = typeof val === "object" && val !== null;
-        return ret;
-    };
-    imports.wbg.__wbg_process_298734cf255a885d = function (arg0) {
-        const ret = getObject(arg0).process;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_versions_e2e78e134e3e5d01 = function (arg0) {
-        const ret = getObject(arg0).versions;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_node_1cd7a5d853dbea79 = function (arg0) {
-        const ret = getObject(arg0).node;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbindgen_is_string = function (arg0) {
-        const ret = typeof getObject(arg0) === "string";
-        return ret;
-    };
-    imports.wbg.__wbg_msCrypto_bcb970640f50a1e8 = function (arg0) {
-        const ret = getObject(arg0).msCrypto;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_require_8f08ceecec0f4fee = function () {
-        return handleError(function () {
-            const ret = module.require;
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbindgen_is_function = function (arg0) {
-        const ret = typeof getObject(arg0) === "function";
-        return ret;
-    };
-    imports.wbg.__wbg_getRandomValues_37fa2ca9e4e07fab = function () {
-        return handleError(function (arg0, arg1) {
-            getObject(arg0).getRandomValues(getObject(arg1));
-        }, arguments);
-    };
-    imports.wbg.__wbg_randomFillSync_dc1e9a60c158336d = function () {
-        return handleError(function (arg0, arg1) {
-            getObject(arg0).randomFillSync(takeObject(arg1));
-        }, arguments);
-    };
-    imports.wbg.__wbg_new_898a68150f225f2e = function () {
-        const ret = new Array();
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_newnoargs_581967eacc0e2604 = function (arg0, arg1) {
-        const ret = new Function(getStringFromWasm0(arg0, arg1));
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_call_cb65541d95d71282 = function () {
-        return handleError(function (arg0, arg1) {
-            const ret = getObject(arg0).call(getObject(arg1));
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbindgen_object_clone_ref = function (arg0) {
-        const ret = getObject(arg0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_self_1ff1d729e9aae938 = function () {
-        return handleError(function () {
-            const ret = self.self;
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbg_window_5f4faef6c12b79ec = function () {
-        return handleError(function () {
-            const ret = window.window;
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbg_globalThis_1d39714405582d3c = function () {
-        return handleError(function () {
-            const ret = globalThis.globalThis;
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbg_global_651f05c6a0944d1c = function () {
-        return handleError(function () {
-            const ret = global.global;
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbindgen_is_undefined = function (arg0) {
-        const ret = getObject(arg0) === undefined;
-        return ret;
-    };
-    imports.wbg.__wbg_newwithlength_3ec098a360da1909 = function (arg0) {
-        const ret = new Array(arg0 >>> 0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_set_502d29070ea18557 = function (arg0, arg1, arg2) {
-        getObject(arg0)[arg1 >>> 0] = takeObject(arg2);
-    };
-    imports.wbg.__wbg_push_ca1c26067ef907ac = function (arg0, arg1) {
-        const ret = getObject(arg0).push(getObject(arg1));
-        return ret;
-    };
-    imports.wbg.__wbg_call_01734de55d61e11d = function () {
-        return handleError(function (arg0, arg1, arg2) {
-            const ret = getObject(arg0).call(getObject(arg1), getObject(arg2));
-            return addHeapObject(ret);
-        }, arguments);
-    };
-    imports.wbg.__wbg_buffer_085ec1f694018c4f = function (arg0) {
-        const ret = getObject(arg0).buffer;
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_newwithbyteoffsetandlength_6da8e527659b86aa = function (
-        arg0,
-        arg1,
-        arg2
-    ) {
-        const ret = new Uint8Array(getObject(arg0), arg1 >>> 0, arg2 >>> 0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_new_8125e318e6245eed = function (arg0) {
-        const ret = new Uint8Array(getObject(arg0));
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_set_5cf90238115182c3 = function (arg0, arg1, arg2) {
-        getObject(arg0).set(getObject(arg1), arg2 >>> 0);
-    };
-    imports.wbg.__wbg_newwithlength_e5d69174d6984cd7 = function (arg0) {
-        const ret = new Uint8Array(arg0 >>> 0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_subarray_13db269f57aa838d = function (arg0, arg1, arg2) {
-        const ret = getObject(arg0).subarray(arg1 >>> 0, arg2 >>> 0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbindgen_throw = function (arg0, arg1) {
-        throw new Error(getStringFromWasm0(arg0, arg1));
-    };
-    imports.wbg.__wbindgen_memory = function () {
-        const ret = wasm.memory;
-        return addHeapObject(ret);
-    };
-
-    return imports;
-}
-
-function __wbg_init_memory(imports, maybe_memory) {}
-
-function __wbg_finalize_init(instance, module) {
-    wasm = instance.exports;
-    __wbg_init.__wbindgen_wasm_module = module;
-    cachedUint8Memory0 = null;
-
-    return wasm;
-}
-
-function initSync(module) {
-    if (wasm !== undefined) return wasm;
-
-    const imports = __wbg_get_imports();
-
-    __wbg_init_memory(imports);
-
-    if (!(module instanceof WebAssembly.Module)) {
-        module = new WebAssembly.Module(module);
-    }
-
-    const instance = new WebAssembly.Instance(module, imports);
-
-    return __wbg_finalize_init(instance, module);
-}
-
-async function __wbg_init(input) {
-    if (wasm !== undefined) return wasm;
-
-    if (typeof input === "undefined") {
-        input = new URL("npmm_bg.wasm", import.meta.url);
-    }
-    const imports = __wbg_get_imports();
-
-    if (
-        typeof input === "string" ||
-        (typeof Request === "function" && input instanceof Request) ||
-        (typeof URL === "function" && input instanceof URL)
-    ) {
-        input = fetch(input);
-    }
-
-    __wbg_init_memory(imports);
-
-    const { instance, module } = await __wbg_load(await input, imports);
-
-    return __wbg_finalize_init(instance, module);
-}
-
-export { initSync };
-export default __wbg_init;
@@ -0,0 +1,397 @@
+// Searches that reduce an N-player game to two sides so that full alpha-beta pruning applies:
+// the root player maximizes its own score and the opponents minimize it.
+
+use crate::clock::now_ms;
+use crate::game::Game;
+use crate::game_elements::{GameStatus, Move, Player};
+use crate::search::{SearchBudget, SearchLimits, SearchResult, SearchStats, Searcher};
+
+struct AlphaBetaContext {
+    root_player: Player,
+    budget: SearchBudget,
+    stats: SearchStats,
+    // Depth of the current iteration
+    search_depth: usize,
+    // Whether the current iteration evaluated any non-terminal leaf
+    depth_limited: bool,
+    // Triangular table of the best line found below each ply, and the score vector at its end
+    principal_variations: Vec<Vec<Move>>,
+    line_scores: Vec<Vec<f64>>,
+}
+
+impl AlphaBetaContext {
+    fn leaf(&mut self, ply: usize, score: Vec<f64>) -> f64 {
+        self.stats.leaf_evaluations += 1;
+        self.stats.max_depth_reached = self.stats.max_depth_reached.max(ply);
+        self.principal_variations[ply].clear();
+        let root_score = score[self.root_player.to_usize()];
+        self.line_scores[ply] = score;
+        root_score
+    }
+
+    // Makes the line through `best_move` and the best line of the child the best line at `ply`
+    fn update_principal_variation(&mut self, ply: usize, best_move: Move) {
+        let (parents, children) = self.principal_variations.split_at_mut(ply + 1);
+        let principal_variation = &mut parents[ply];
+        principal_variation.clear();
+        principal_variation.push(best_move);
+        principal_variation.extend_from_slice(&children[0]);
+        self.line_scores[ply] = self.line_scores[ply + 1].clone();
+    }
+}
+
+type ChildSearch = fn(&mut Game, &mut AlphaBetaContext, usize, f64, f64) -> f64;
+
+// Paranoid search: every opponent is assumed to play only to minimize the root player's score
+pub struct ParanoidSearcher;
+
+impl Searcher for ParanoidSearcher {
+    fn search(&mut self, game: &mut Game, limits: &SearchLimits) -> SearchResult {
+        iterative_alpha_beta(game, limits, paranoid)
+    }
+}
+
+// Best-Reply Search: at each opponent layer, only the single strongest reply among all
+// opponents is played and the others effectively pass
+pub struct BestReplySearcher;
+
+impl Searcher for BestReplySearcher {
+    fn search(&mut self, game: &mut Game, limits: &SearchLimits) -> SearchResult {
+        iterative_alpha_beta(game, limits, |game, ctx, depth, alpha, beta| {
+            best_reply(game, ctx, depth, alpha, beta, false)
+        })
+    }
+}
+
+fn iterative_alpha_beta(
+    game: &mut Game,
+    limits: &SearchLimits,
+    child_search: ChildSearch,
+) -> SearchResult {
+    let start_ms = now_ms();
+    let mut ctx = AlphaBetaContext {
+        root_player: game.get_to_move(),
+        budget: SearchBudget::new(limits),
+        stats: SearchStats::default(),
+        search_depth: 0,
+        depth_limited: false,
+        principal_variations: Vec::new(),
+        line_scores: Vec::new(),
+    };
+    let mut moves = game.get_valid_moves();
+    let mut best = (Vec::new(), game.get_score(), 0);
+    for depth in 1..=limits.max_depth {
+        ctx.budget.abortable = !best.0.is_empty();
+        ctx.depth_limited = false;
+        ctx.search_depth = depth;
+        ctx.principal_variations.resize(depth + 1, Vec::new());
+        ctx.line_scores.resize(depth + 1, Vec::new());
+
+        let mut alpha = f64::NEG_INFINITY;
+        let mut best_move_idx = None;
+        for (move_idx, game_move) in moves.iter().enumerate() {
+            game.transition(game_move);
+            let score = child_search(game, &mut ctx, depth - 1, alpha, f64::INFINITY);
+            game.undo_transition();
+            if ctx.budget.is_aborted() {
+                break;
+            }
+            if best_move_idx.is_none() || score > alpha {
+                alpha = score;
+                best_move_idx = Some(move_idx);
+                ctx.update_principal_variation(0, game_move.clone());
+            }
+        }
+        if ctx.budget.is_aborted() {
+            break;
+        }
+        let Some(best_move_idx) = best_move_idx else {
+            break;
+        };
+        best = (
+            ctx.principal_variations[0].clone(),
+            ctx.line_scores[0].clone(),
+            depth,
+        );
+
+        // Search the best move of this iteration first in the next one
+        moves[..=best_move_idx].rotate_right(1);
+
+        if !ctx.depth_limited {
+            break;
+        }
+    }
+    let (principal_variation, score, depth) = best;
+    SearchResult::new(
+        principal_variation,
+        score,
+        depth,
+        ctx.budget.get_nodes(),
+        &ctx.stats,
+        start_ms,
+    )
+}
+
+// Scores terminal states and depth cutoffs from the root player's perspective
+fn leaf_score(game: &Game, ctx: &mut AlphaBetaContext, depth: usize) -> Option<f64> {
+    let ply = ctx.search_depth - depth;
+    if game.get_game_status() != GameStatus::Ongoing {
+        return Some(ctx.leaf(ply, game.get_score()));
+    }
+    if depth == 0 {
+        ctx.depth_limited = true;
+        return Some(ctx.leaf(ply, game.get_score()));
+    }
+    None
+}
+
+fn paranoid(
+    game: &mut Game,
+    ctx: &mut AlphaBetaContext,
+    depth: usize,
+    mut alpha: f64,
+    mut beta: f64,
+) -> f64 {
+    if ctx.budget.visit_node() {
+        return 0.0;
+    }
+    if let Some(score) = leaf_score(game, ctx, depth) {
+        return score;
+    }
+
+    let ply = ctx.search_depth - depth;
+    let maximizing = game.get_to_move() == ctx.root_player;
+    let mut best = if maximizing {
+        f64::NEG_INFINITY
+    } else {
+        f64::INFINITY
+    };
+    for game_move in game.get_valid_moves() {
+        game.transition(&game_move);
+        let score = paranoid(game, ctx, depth - 1, alpha, beta);
+        game.undo_transition();
+        if ctx.budget.is_aborted() {
+            return 0.0;
+        }
+        if (maximizing && score > best) || (!maximizing && score < best) {
+            best = score;
+            ctx.update_principal_variation(ply, game_move);
+        }
+        if maximizing {
+            alpha = alpha.max(best);
+        } else {
+            beta = beta.min(best);
+        }
+        if alpha >= beta {
+            ctx.stats.prunes += 1;
+            break;
+        }
+    }
+
+    // An ongoing game without moves is scored as it stands
+    if best.is_infinite() {
+        return ctx.leaf(ply, game.get_score());
+    }
+    best
+}
+
+fn best_reply(
+    game: &mut Game,
+    ctx: &mut AlphaBetaContext,
+    depth: usize,
+    mut alpha: f64,
+    mut beta: f64,
+    maximizing: bool,
+) -> f64 {
+    if ctx.budget.visit_node() {
+        return 0.0;
+    }
+    if let Some(score) = leaf_score(game, ctx, depth) {
+        return score;
+    }
+
+    // Layers alternate between the root player and all opponents, whoever the spec says is next
+    let ply = ctx.search_depth - depth;
+    let movers: Vec<Player> = if maximizing {
+        vec![ctx.root_player]
+    } else {
+        (0..game.get_num_players())
+            .map(Player)
+            .filter(|&player| player != ctx.root_player)
+            .collect()
+    };
+    let mut best = if maximizing {
+        f64::NEG_INFINITY
+    } else {
+        f64::INFINITY
+    };
+    for mover in movers {
+        let out_of_turn = game.get_to_move() != mover;
+        if out_of_turn {
+            game.set_to_move(mover);
+        }
+        for game_move in game.get_valid_moves() {
+            game.transition(&game_move);
+            let score = best_reply(game, ctx, depth - 1, alpha, beta, !maximizing);
+            game.undo_transition();
+            if ctx.budget.is_aborted() {
+                break;
+            }
+            if (maximizing && score > best) || (!maximizing && score < best) {
+                best = score;
+                ctx.update_principal_variation(ply, game_move);
+            }
+            if maximizing {
+                alpha = alpha.max(best);
+            } else {
+                beta = beta.min(best);
+            }
+            if alpha >= beta {
+                ctx.stats.prunes += 1;
+                break;
+            }
+        }
+        if out_of_turn {
+            game.undo_transition();
+        }
+        if ctx.budget.is_aborted() || alpha >= beta {
+            break;
+        }
+    }
+    if ctx.budget.is_aborted() {
+        return 0.0;
+    }
+
+    // Nobody on this side could move, so the position is scored as it stands
+    if best.is_infinite() {
+        return ctx.leaf(ply, game.get_score());
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::test_support::{new_game, unlimited_limits, OPENINGS};
+
+    fn search(game: &mut Game, searcher: &mut dyn Searcher, max_depth: usize) -> SearchResult {
+        searcher.search(game, &unlimited_limits(max_depth, true))
+    }
+
+    // Paranoid minimax without pruning
+    fn plain_paranoid(game: &mut Game, root_player: Player, depth: usize) -> f64 {
+        let root_score = game.get_score()[root_player.to_usize()];
+        if depth == 0 || game.get_game_status() != GameStatus::Ongoing {
+            return root_score;
+        }
+        let scores: Vec<f64> = game
+            .get_valid_moves()
+            .iter()
+            .map(|game_move| {
+                game.transition(game_move);
+                let score = plain_paranoid(game, root_player, depth - 1);
+                game.undo_transition();
+                score
+            })
+            .collect();
+        let best = if game.get_to_move() == root_player {
+            scores.into_iter().fold(f64::NEG_INFINITY, f64::max)
+        } else {
+            scores.into_iter().fold(f64::INFINITY, f64::min)
+        };
+        if best.is_infinite() {
+            root_score
+        } else {
+            best
+        }
+    }
+
+    // Best-Reply Search without pruning
+    fn plain_best_reply(
+        game: &mut Game,
+        root_player: Player,
+        depth: usize,
+        maximizing: bool,
+    ) -> f64 {
+        let root_score = game.get_score()[root_player.to_usize()];
+        if depth == 0 || game.get_game_status() != GameStatus::Ongoing {
+            return root_score;
+        }
+        let movers: Vec<Player> = (0..game.get_num_players())
+            .map(Player)
+            .filter(|&player| (player == root_player) == maximizing)
+            .collect();
+        let mut scores = Vec::new();
+        for mover in movers {
+            let out_of_turn = game.get_to_move() != mover;
+            if out_of_turn {
+                game.set_to_move(mover);
+            }
+            for game_move in game.get_valid_moves() {
+                game.transition(&game_move);
+                scores.push(plain_best_reply(game, root_player, depth - 1, !maximizing));
+                game.undo_transition();
+            }
+            if out_of_turn {
+                game.undo_transition();
+            }
+        }
+        let best = if maximizing {
+            scores.into_iter().fold(f64::NEG_INFINITY, f64::max)
+        } else {
+            scores.into_iter().fold(f64::INFINITY, f64::min)
+        };
+        if best.is_infinite() {
+            root_score
+        } else {
+            best
+        }
+    }
+
+    #[test]
+    fn paranoid_matches_plain_minimax() {
+        for num_players in 2..=3 {
+            for opening in OPENINGS {
+                let mut game = new_game(num_players, opening);
+                let root_player = game.get_to_move();
+                for depth in 1..=3 {
+                    let result = search(&mut game, &mut ParanoidSearcher, depth);
+                    assert_eq!(result.depth, depth);
+                    assert_eq!(
+                        result.score[root_player.to_usize()],
+                        plain_paranoid(&mut game, root_player, depth)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn best_reply_matches_plain_best_reply_search() {
+        for num_players in 2..=3 {
+            for opening in OPENINGS {
+                let mut game = new_game(num_players, opening);
+                let root_player = game.get_to_move();
+                for depth in 1..=3 {
+                    let result = search(&mut game, &mut BestReplySearcher, depth);
+                    assert_eq!(result.depth, depth);
+                    assert_eq!(
+                        result.score[root_player.to_usize()],
+                        plain_best_reply(&mut game, root_player, depth, true)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn searches_leave_the_game_unchanged() {
+        let mut game = new_game(3, &[3, 3, 2]);
+        let hash = game.get_hash();
+        let move_num = game.get_move_num();
+        search(&mut game, &mut ParanoidSearcher, 3);
+        search(&mut game, &mut BestReplySearcher, 3);
+        assert_eq!(game.get_hash(), hash);
+        assert_eq!(game.get_move_num(), move_num);
+    }
+}
@@ -0,0 +1,226 @@
+use std::ops::{Index, IndexMut};
+
+use crate::game_elements::BoardCell;
+
+// Steps to the eight surrounding cells, as (row, col) offsets
+pub const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Steps to the four cells sharing an edge
+pub const ORTHOGONAL_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+// Directions in which lines are read: along a row, down a column and down both diagonals
+pub const LINE_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+// A rectangular grid of cells stored row by row in one buffer, with (0, 0) at the top left
+#[derive(Clone, PartialEq)]
+pub struct Board {
+    num_rows: usize,
+    num_cols: usize,
+    cells: Vec<BoardCell>,
+}
+
+impl Board {
+    pub fn new(num_rows: usize, num_cols: usize) -> Self {
+        Board {
+            num_rows,
+            num_cols,
+            cells: vec![BoardCell::Empty; num_rows * num_cols],
+        }
+    }
+
+    pub fn get_num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn get_num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    // Takes signed coordinates so that callers can step off the board and check afterwards
+    pub fn in_bounds(&self, row: i32, col: i32) -> bool {
+        row >= 0 && col >= 0 && (row as usize) < self.num_rows && (col as usize) < self.num_cols
+    }
+
+    // Returns the previous contents of the cell; panics when the position is off the board
+    pub fn set(&mut self, row: usize, col: usize, cell: BoardCell) -> BoardCell {
+        std::mem::replace(&mut self[(row, col)], cell)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[BoardCell]> {
+        self.cells.chunks(self.num_cols.max(1))
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &BoardCell> + '_ {
+        (0..self.num_rows).map(move |row| &self[(row, col)])
+    }
+
+    // Every cell with its position, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &BoardCell)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| (idx / self.num_cols, idx % self.num_cols, cell))
+    }
+
+    // Positions of the cells one step away in the given directions that are on the board
+    pub fn neighbors<'a>(
+        &'a self,
+        row: usize,
+        col: usize,
+        directions: &'a [(i32, i32)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        directions.iter().filter_map(move |&(dr, dc)| {
+            let (r, c) = (row as i32 + dr, col as i32 + dc);
+            self.in_bounds(r, c).then_some((r as usize, c as usize))
+        })
+    }
+
+    // The cells from (row, col) outwards in one direction up to the edge, not including the start
+    pub fn ray(
+        &self,
+        row: usize,
+        col: usize,
+        direction: (i32, i32),
+    ) -> impl Iterator<Item = (usize, usize, &BoardCell)> + '_ {
+        let (dr, dc) = direction;
+        (1..)
+            .map(move |step| (row as i32 + dr * step, col as i32 + dc * step))
+            .take_while(|&(r, c)| self.in_bounds(r, c))
+            .map(|(r, c)| (r as usize, c as usize, &self[(r as usize, c as usize)]))
+    }
+
+    // Every run of `length` consecutive positions along a row, column or diagonal, e.g. for
+    // checking n-in-a-row
+    pub fn lines(&self, length: usize) -> impl Iterator<Item = Vec<(usize, usize)>> + '_ {
+        let reach = length as i32 - 1;
+        self.cells().flat_map(move |(row, col, _)| {
+            LINE_DIRECTIONS.iter().filter_map(move |&(dr, dc)| {
+                let (end_row, end_col) = (row as i32 + dr * reach, col as i32 + dc * reach);
+                if length == 0 || !self.in_bounds(end_row, end_col) {
+                    return None;
+                }
+                let line = (0..length as i32)
+                    .map(|k| {
+                        (
+                            (row as i32 + dr * k) as usize,
+                            (col as i32 + dc * k) as usize,
+                        )
+                    })
+                    .collect();
+                Some(line)
+            })
+        })
+    }
+}
+
+impl Index<(usize, usize)> for Board {
+    type Output = BoardCell;
+
+    fn index(&self, (row, col): (usize, usize)) -> &BoardCell {
+        assert!(
+            row < self.num_rows && col < self.num_cols,
+            "Cell ({}, {}) is off the {}x{} board",
+            row,
+            col,
+            self.num_rows,
+            self.num_cols
+        );
+        &self.cells[row * self.num_cols + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Board {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut BoardCell {
+        assert!(
+            row < self.num_rows && col < self.num_cols,
+            "Cell ({}, {}) is off the {}x{} board",
+            row,
+            col,
+            self.num_rows,
+            self.num_cols
+        );
+        &mut self.cells[row * self.num_cols + col]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_bounds_stops_at_every_edge() {
+        let board = Board::new(3, 4);
+        for (row, col) in [(0, 0), (0, 3), (2, 0), (2, 3)] {
+            assert!(board.in_bounds(row, col));
+        }
+        for (row, col) in [(-1, 0), (0, -1), (3, 0), (0, 4), (3, 4), (-1, -1)] {
+            assert!(!board.in_bounds(row, col));
+        }
+    }
+
+    #[test]
+    fn neighbors_leave_out_cells_off_the_board() {
+        let board = Board::new(3, 4);
+        let neighbors = |row, col, directions| board.neighbors(row, col, directions).count();
+        assert_eq!(neighbors(0, 0, &DIRECTIONS), 3);
+        assert_eq!(neighbors(2, 3, &DIRECTIONS), 3);
+        assert_eq!(neighbors(0, 1, &DIRECTIONS), 5);
+        assert_eq!(neighbors(1, 1, &DIRECTIONS), 8);
+        assert_eq!(neighbors(0, 0, &ORTHOGONAL_DIRECTIONS), 2);
+        assert_eq!(
+            board
+                .neighbors(0, 3, &ORTHOGONAL_DIRECTIONS)
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (1, 3)]
+        );
+        // A single cell has no neighbors at all
+        assert_eq!(Board::new(1, 1).neighbors(0, 0, &DIRECTIONS).count(), 0);
+    }
+
+    #[test]
+    fn rays_run_to_the_edge() {
+        let board = Board::new(3, 4);
+        let ray = |row, col, direction| {
+            board
+                .ray(row, col, direction)
+                .map(|(r, c, _)| (r, c))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ray(0, 0, (0, 1)), vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(ray(0, 0, (1, 1)), vec![(1, 1), (2, 2)]);
+        assert_eq!(ray(2, 3, (-1, -1)), vec![(1, 2), (0, 1)]);
+        // Pointing off the board from a corner or an edge
+        assert!(ray(0, 0, (-1, 0)).is_empty());
+        assert!(ray(0, 0, (1, -1)).is_empty());
+        assert!(ray(1, 3, (0, 1)).is_empty());
+    }
+
+    #[test]
+    fn lines_fit_inside_the_board() {
+        let board = Board::new(3, 4);
+        let lines: Vec<_> = board.lines(3).collect();
+        // Two per row, four per column and two down each diagonal
+        assert_eq!(lines.len(), 3 * 2 + 4 + 2 + 2);
+        assert!(lines.contains(&vec![(0, 1), (0, 2), (0, 3)]));
+        assert!(lines.contains(&vec![(0, 3), (1, 2), (2, 1)]));
+        assert!(lines
+            .iter()
+            .flatten()
+            .all(|&(row, col)| board.in_bounds(row as i32, col as i32)));
+        // Longer than the board is in every direction
+        assert_eq!(board.lines(5).count(), 0);
+        assert_eq!(board.lines(4).count(), 3);
+        // Each cell is a line of one in every direction
+        assert_eq!(board.lines(1).count(), 3 * 4 * 4);
+        assert_eq!(board.lines(0).count(), 0);
+    }
+}
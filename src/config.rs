@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+// Names under which the dedicated GameConfig fields appear in a ConfigSchema
+pub const NUM_ROWS: &str = "num_rows";
+pub const NUM_COLS: &str = "num_cols";
+pub const WIN_LENGTH: &str = "win_length";
+
+// Rule parameters chosen when a game is created. Anything left out falls back to the default
+// the spec declares in its ConfigSchema.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GameConfig {
+    pub num_rows: Option<usize>,
+    pub num_cols: Option<usize>,
+    // How many pieces in a line win, for games that have such a rule
+    pub win_length: Option<usize>,
+    // Any other parameter the spec declares, by name
+    pub params: HashMap<String, i64>,
+}
+
+// A parameter a spec accepts, with its default and the inclusive range of supported values
+#[derive(Serialize, Clone)]
+pub struct ConfigParam {
+    pub name: String,
+    pub default: i64,
+    pub min: i64,
+    pub max: i64,
+    // Supported values go up from min in steps of this size, e.g. 2 for even board sizes
+    pub step: i64,
+}
+
+impl ConfigParam {
+    pub fn new(name: &str, default: i64, min: i64, max: i64) -> Self {
+        ConfigParam {
+            name: name.to_string(),
+            default,
+            min,
+            max,
+            step: 1,
+        }
+    }
+
+    pub fn with_step(mut self, step: i64) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+// Everything about a game that can be configured; specs with fixed rules have no params
+#[derive(Serialize, Clone, Default)]
+pub struct ConfigSchema {
+    pub params: Vec<ConfigParam>,
+}
+
+impl GameConfig {
+    // The value of a parameter, whether it's one of the dedicated fields or in params
+    pub fn get(&self, name: &str) -> Option<i64> {
+        match name {
+            NUM_ROWS => self.num_rows.map(|value| value as i64),
+            NUM_COLS => self.num_cols.map(|value| value as i64),
+            WIN_LENGTH => self.win_length.map(|value| value as i64),
+            _ => self.params.get(name).copied(),
+        }
+    }
+
+    fn set(&mut self, name: &str, value: i64) {
+        match name {
+            NUM_ROWS => self.num_rows = Some(value as usize),
+            NUM_COLS => self.num_cols = Some(value as usize),
+            WIN_LENGTH => self.win_length = Some(value as usize),
+            _ => {
+                self.params.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    // Fills in the defaults of the schema and checks every given value against it. Parameters
+    // the schema doesn't declare are rejected rather than silently ignored.
+    pub fn resolve(&self, schema: &ConfigSchema) -> Result<GameConfig, String> {
+        let declared = |name: &str| schema.params.iter().any(|param| param.name == name);
+        let given_names = [NUM_ROWS, NUM_COLS, WIN_LENGTH]
+            .into_iter()
+            .filter(|&name| self.get(name).is_some())
+            .chain(self.params.keys().map(|name| name.as_str()));
+        for name in given_names {
+            if !declared(name) {
+                return Err(format!("This game has no parameter named {}", name));
+            }
+        }
+
+        let mut resolved = GameConfig::default();
+        for param in &schema.params {
+            let value = self.get(&param.name).unwrap_or(param.default);
+            if value < param.min || value > param.max {
+                return Err(format!(
+                    "{} must be between {} and {}, got {}",
+                    param.name, param.min, param.max, value
+                ));
+            }
+            if (value - param.min) % param.step != 0 {
+                return Err(format!(
+                    "{} must go up from {} in steps of {}, got {}",
+                    param.name, param.min, param.step, value
+                ));
+            }
+            resolved.set(&param.name, value);
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema {
+            params: vec![
+                ConfigParam::new(NUM_ROWS, 8, 4, 16).with_step(2),
+                ConfigParam::new("handicap", 0, 0, 3),
+            ],
+        }
+    }
+
+    fn with_rows(num_rows: usize) -> GameConfig {
+        GameConfig {
+            num_rows: Some(num_rows),
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    fn resolve_fills_in_defaults() {
+        let config = GameConfig {
+            params: HashMap::from([("handicap".to_string(), 2)]),
+            ..GameConfig::default()
+        };
+        let resolved = config.resolve(&schema()).unwrap();
+        assert_eq!(resolved.num_rows, Some(8));
+        assert_eq!(resolved.get("handicap"), Some(2));
+        assert_eq!(resolved.num_cols, None);
+    }
+
+    #[test]
+    fn resolve_rejects_values_the_schema_doesnt_allow() {
+        assert_eq!(with_rows(10).resolve(&schema()).unwrap().num_rows, Some(10));
+        assert!(with_rows(2).resolve(&schema()).is_err());
+        assert!(with_rows(18).resolve(&schema()).is_err());
+        // Off the steps up from the minimum
+        assert!(with_rows(7).resolve(&schema()).is_err());
+
+        let undeclared = GameConfig {
+            win_length: Some(4),
+            ..GameConfig::default()
+        };
+        assert!(undeclared.resolve(&schema()).is_err());
+    }
+}
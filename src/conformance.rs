@@ -0,0 +1,405 @@
+// Checks that a GameSpec keeps the promises the searches rely on, by playing many seeded random
+// games with it. Generated specs are run through this natively before they are shipped, with
+// `cargo test conformance`.
+
+use std::fmt;
+use std::mem::discriminant;
+use std::panic::{self, AssertUnwindSafe};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::config::GameConfig;
+use crate::error::GameError;
+use crate::game::Game;
+use crate::game_elements::{GameStatus, Move, Outcome, Player};
+use crate::game_spec::GameSpec;
+
+pub struct ConformanceOptions {
+    pub num_playouts: usize,
+    // A playout that is still going after this many moves counts as a game that never ends
+    pub max_moves: usize,
+    // Playout i is played with seed + i, so any failure can be replayed
+    pub seed: u64,
+}
+
+impl Default for ConformanceOptions {
+    fn default() -> Self {
+        ConformanceOptions {
+            num_playouts: 1000,
+            max_moves: 10_000,
+            seed: 0,
+        }
+    }
+}
+
+// A broken promise, found after some sequence of moves
+#[derive(PartialEq, Clone, Debug)]
+pub enum Violation {
+    // Game::new refused the spec with these players and config
+    NotCreated(GameError),
+    // One of the spec's functions panicked
+    Panicked {
+        during: &'static str,
+        message: String,
+    },
+    MoveNumNotAdvanced {
+        before: usize,
+        after: usize,
+    },
+    // transition handed the turn to a player who isn't in the game
+    BadNextPlayer(Player),
+    // undo_transition left a different state than the one before the move
+    UndoMismatch,
+    // Replaying the move after undoing it led somewhere else
+    NonDeterministic,
+    // The game is ongoing but the player to move can't do anything
+    NoValidMoves,
+    // A finished game whose result doesn't add up, e.g. two winners
+    ContradictoryResult(String),
+    DidNotTerminate(usize),
+}
+
+pub struct ConformanceFailure {
+    pub violation: Violation,
+    // The shortest sequence of moves found that leads to the violation, from the initial state
+    pub moves: Vec<Move>,
+    // Seed of the playout that found it
+    pub seed: u64,
+}
+
+// Totals over all playouts, for specs that pass
+#[derive(Default, Debug)]
+pub struct ConformanceReport {
+    pub num_playouts: usize,
+    pub num_moves: usize,
+    pub num_ties: usize,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::NotCreated(err) => write!(f, "The game can't be created: {}", err),
+            Violation::Panicked { during, message } => {
+                write!(f, "Panicked during {}: {}", during, message)
+            }
+            Violation::MoveNumNotAdvanced { before, after } => write!(
+                f,
+                "The move number went from {} to {} instead of {}",
+                before,
+                after,
+                before + 1
+            ),
+            Violation::BadNextPlayer(player) => {
+                write!(f, "Player {} isn't in the game", player.to_usize())
+            }
+            Violation::UndoMismatch => write!(f, "Undoing the move didn't restore the state"),
+            Violation::NonDeterministic => {
+                write!(f, "Replaying the move gave a different state")
+            }
+            Violation::NoValidMoves => {
+                write!(f, "The game is ongoing but there are no valid moves")
+            }
+            Violation::ContradictoryResult(message) => {
+                write!(f, "Contradictory result: {}", message)
+            }
+            Violation::DidNotTerminate(max_moves) => {
+                write!(f, "The game didn't end within {} moves", max_moves)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConformanceFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} after the moves {:?} (seed {})",
+            self.violation, self.moves, self.seed
+        )
+    }
+}
+
+// How a single playout went
+enum Playout {
+    Finished(GameStatus),
+    // The chooser had no move to give, when replaying a sequence that stopped being valid
+    Stopped,
+    Violated(Violation),
+}
+
+// Runs one of the spec's functions, turning a panic into a violation
+fn guard<T>(during: &'static str, f: impl FnOnce() -> T) -> Result<T, Violation> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Violation::Panicked { during, message }
+    })
+}
+
+// The result functions of a finished game have to agree with each other, whichever of them
+// get_game_status happens to check first
+fn check_result(game: &Game, status: &GameStatus) -> Result<(), Violation> {
+    let num_players = game.get_num_players();
+    if let GameStatus::Over(outcome) = status {
+        let num_entries = match outcome {
+            Outcome::Placements(placements) => placements.len(),
+            Outcome::Points(values) | Outcome::Utilities(values) => values.len(),
+        };
+        if num_entries != num_players {
+            return Err(Violation::ContradictoryResult(format!(
+                "the outcome has {} entries for {} players",
+                num_entries, num_players
+            )));
+        }
+        return Ok(());
+    }
+    let is_tie = guard("is_tie", || game.is_tie())?;
+    let mut winners = Vec::new();
+    for player_idx in 0..num_players {
+        if guard("is_win", || game.is_win(Player(player_idx)))? {
+            winners.push(player_idx);
+        }
+    }
+    if winners.len() > 1 {
+        return Err(Violation::ContradictoryResult(format!(
+            "players {:?} all win",
+            winners
+        )));
+    }
+    if is_tie && !winners.is_empty() {
+        return Err(Violation::ContradictoryResult(format!(
+            "player {} wins a tied game",
+            winners[0]
+        )));
+    }
+    Ok(())
+}
+
+// Plays a copy of the game to the end, checking every move on the way. Each move is chosen from
+// the valid moves by `choose`, given how many moves were played before it. Returns the moves
+// played along with how the playout went.
+fn play(
+    initial: &Game,
+    max_moves: usize,
+    mut choose: impl FnMut(usize, &[Move]) -> Option<Move>,
+) -> (Vec<Move>, Playout) {
+    let mut game = initial.fork();
+    let mut played = Vec::new();
+    let num_players = game.get_num_players();
+    let playout = loop {
+        let status = match guard("get_game_status", || game.get_game_status()) {
+            Ok(status) => status,
+            Err(violation) => break Playout::Violated(violation),
+        };
+        if status != GameStatus::Ongoing {
+            match check_result(&game, &status) {
+                Ok(()) => break Playout::Finished(status),
+                Err(violation) => break Playout::Violated(violation),
+            }
+        }
+        if played.len() >= max_moves {
+            break Playout::Violated(Violation::DidNotTerminate(max_moves));
+        }
+        let moves = match guard("get_valid_moves", || game.get_valid_moves()) {
+            Ok(moves) => moves,
+            Err(violation) => break Playout::Violated(violation),
+        };
+        if moves.is_empty() {
+            break Playout::Violated(Violation::NoValidMoves);
+        }
+        let Some(game_move) = choose(played.len(), &moves) else {
+            break Playout::Stopped;
+        };
+        played.push(game_move.clone());
+
+        let before = game.get_state().clone();
+        if let Err(violation) = guard("transition", || game.transition(&game_move)) {
+            break Playout::Violated(violation);
+        }
+        let after = game.get_state().clone();
+        if after.get_move_num() != before.get_move_num() + 1 {
+            break Playout::Violated(Violation::MoveNumNotAdvanced {
+                before: before.get_move_num(),
+                after: after.get_move_num(),
+            });
+        }
+        if after.get_to_move().to_usize() >= num_players {
+            break Playout::Violated(Violation::BadNextPlayer(after.get_to_move()));
+        }
+        game.undo_transition();
+        if *game.get_state() != before {
+            break Playout::Violated(Violation::UndoMismatch);
+        }
+        if let Err(violation) = guard("transition", || game.transition(&game_move)) {
+            break Playout::Violated(violation);
+        }
+        if *game.get_state() != after {
+            break Playout::Violated(Violation::NonDeterministic);
+        }
+    };
+    (played, playout)
+}
+
+// Looks for a shorter sequence of moves that still breaks the same promise, by leaving out one
+// move at a time while the rest stays valid
+fn shrink(
+    initial: &Game,
+    max_moves: usize,
+    moves: Vec<Move>,
+    violation: Violation,
+) -> (Vec<Move>, Violation) {
+    let mut shortest = (moves, violation);
+    let mut skipped = 0;
+    while skipped < shortest.0.len() {
+        let mut candidate = shortest.0.clone();
+        candidate.remove(skipped);
+        let (played, playout) = play(initial, max_moves, |move_idx, valid_moves| {
+            candidate
+                .get(move_idx)
+                .filter(|game_move| valid_moves.contains(game_move))
+                .cloned()
+        });
+        match playout {
+            Playout::Violated(found) if discriminant(&found) == discriminant(&shortest.1) => {
+                shortest = (played, found);
+            }
+            _ => skipped += 1,
+        }
+    }
+    shortest
+}
+
+// Plays random games with the spec and reports the first broken promise, with the shortest
+// sequence of moves found that leads to it
+pub fn check_conformance(
+    spec: Box<dyn GameSpec>,
+    num_players: usize,
+    config: &GameConfig,
+    options: &ConformanceOptions,
+) -> Result<ConformanceReport, ConformanceFailure> {
+    let initial = Game::new(spec, num_players, config).map_err(|err| ConformanceFailure {
+        violation: Violation::NotCreated(err),
+        moves: Vec::new(),
+        seed: options.seed,
+    })?;
+    let mut report = ConformanceReport::default();
+    for playout_idx in 0..options.num_playouts {
+        let seed = options.seed.wrapping_add(playout_idx as u64);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (moves, playout) = play(&initial, options.max_moves, |_, valid_moves| {
+            valid_moves.choose(&mut rng).cloned()
+        });
+        report.num_playouts += 1;
+        report.num_moves += moves.len();
+        match playout {
+            Playout::Finished(GameStatus::Tie) => report.num_ties += 1,
+            Playout::Finished(_) | Playout::Stopped => {}
+            Playout::Violated(violation) => {
+                let (moves, violation) = shrink(&initial, options.max_moves, moves, violation);
+                return Err(ConformanceFailure {
+                    violation,
+                    moves,
+                    seed,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::game::MoveDelta;
+    use crate::game_elements::BoardCell;
+    use crate::registry::get_games;
+
+    // Playouts per game and player count, fewer than the default to keep the suite quick
+    const NUM_PLAYOUTS: usize = 200;
+
+    #[test]
+    fn registered_games_conform() {
+        let options = ConformanceOptions {
+            num_playouts: NUM_PLAYOUTS,
+            ..ConformanceOptions::default()
+        };
+        for entry in get_games() {
+            let metadata = entry.get_listing().unwrap().metadata;
+            for num_players in metadata.min_players..=metadata.max_players.min(4) {
+                if let Err(failure) = check_conformance(
+                    entry.new_spec(),
+                    num_players,
+                    &GameConfig::default(),
+                    &options,
+                ) {
+                    panic!("{} with {} players: {}", entry.name, num_players, failure);
+                }
+            }
+        }
+    }
+
+    // Fills the board, but panics whenever a piece goes in the center
+    struct BrokenSpec;
+
+    impl GameSpec for BrokenSpec {
+        fn get_initial_board(&self, _config: &GameConfig, _num_players: usize) -> Board {
+            Board::new(3, 3)
+        }
+
+        fn get_initial_to_move(&self) -> Player {
+            Player(0)
+        }
+
+        fn get_valid_moves(&self, game: &Game) -> Vec<Move> {
+            game.get_board()
+                .cells()
+                .filter(|(_, _, cell)| cell.is_empty())
+                .map(|(row, col, _)| Move::place(row, col))
+                .collect()
+        }
+
+        fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta {
+            let (row, col) = game_move.get_destination().unwrap();
+            assert!((row, col) != (1, 1), "the center is off limits");
+            MoveDelta {
+                changed_cells: vec![(row, col, BoardCell::owned_by(game.get_to_move()))],
+                next_to_move: game.get_next_player(),
+            }
+        }
+
+        fn is_win(&self, _game: &Game, _player: Player) -> bool {
+            false
+        }
+
+        fn is_tie(&self, game: &Game) -> bool {
+            game.get_board()
+                .cells()
+                .all(|(_, _, cell)| !cell.is_empty())
+        }
+    }
+
+    #[test]
+    fn reports_shortest_failing_sequence() {
+        let failure = check_conformance(
+            Box::new(BrokenSpec),
+            2,
+            &GameConfig::default(),
+            &ConformanceOptions::default(),
+        )
+        .expect_err("placing in the center panics");
+        assert!(matches!(
+            failure.violation,
+            Violation::Panicked {
+                during: "transition",
+                ..
+            }
+        ));
+        assert_eq!(failure.moves, vec![Move::place(1, 1)]);
+    }
+}
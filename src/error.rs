@@ -0,0 +1,62 @@
+use std::fmt;
+
+use wasm_bindgen::JsValue;
+
+use crate::game_elements::Move;
+
+// Everything that can go wrong when a game is created or played through its public API. The
+// bindings throw these to JS as Errors carrying the message.
+#[derive(PartialEq, Clone, Debug)]
+pub enum GameError {
+    // The move isn't one of the valid moves of the player to move
+    IllegalMove(Move),
+    GameOver,
+    // The player to move has no valid move, and the spec doesn't allow passing
+    NoValidMoves,
+    NothingToUndo,
+    NothingToRedo,
+    BadPlayerCount {
+        num_players: usize,
+        min: usize,
+        max: usize,
+    },
+    // A player number that isn't in the game
+    InvalidPlayer(usize),
+    // The config doesn't fit the spec's schema
+    InvalidConfig(String),
+    // No game is registered under the name
+    UnknownGame(String),
+    // The rule document can't be read or describes a game that can't be played
+    InvalidRules(String),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::IllegalMove(game_move) => write!(f, "Illegal move: {:?}", game_move),
+            GameError::GameOver => write!(f, "The game is already over"),
+            GameError::NoValidMoves => write!(f, "The player to move has no valid moves"),
+            GameError::NothingToUndo => write!(f, "There are no moves to undo"),
+            GameError::NothingToRedo => write!(f, "There are no undone moves to redo"),
+            GameError::BadPlayerCount {
+                num_players,
+                min,
+                max,
+            } => write!(
+                f,
+                "This game needs between {} and {} players, got {}",
+                min, max, num_players
+            ),
+            GameError::InvalidPlayer(player) => write!(f, "There is no player {}", player),
+            GameError::InvalidConfig(message) => write!(f, "Invalid config: {}", message),
+            GameError::UnknownGame(name) => write!(f, "There is no game named {}", name),
+            GameError::InvalidRules(message) => write!(f, "Invalid rules: {}", message),
+        }
+    }
+}
+
+impl From<GameError> for JsValue {
+    fn from(err: GameError) -> JsValue {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
@@ -1,14 +1,27 @@
-use crate::game::GameState;
-use rand::Rng;
+use std::cell::RefCell;
 
+use crate::board::Board;
+use crate::game::Game;
+use crate::game_elements::{BoardCell, GameStatus, Move, Player};
+use crate::othello::OthelloSpec;
+#[cfg(test)]
+use crate::zobrist::mix;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+// Evaluators get the whole game rather than just its state so that they can consult the rules
 pub trait EvaluationFunction {
-    fn evaluate(&self, state: &GameState) -> Vec<f64>;
+    fn evaluate(&self, game: &Game) -> Vec<f64>;
 }
 
+// No spec uses it any more, but it stays around as a baseline to compare heuristics against
+#[allow(dead_code)]
 pub struct RandomEvaluationFunction {
     num_players: usize,
 }
 
+#[allow(dead_code)]
 impl RandomEvaluationFunction {
     pub fn new(num_players: usize) -> Self {
         RandomEvaluationFunction { num_players }
@@ -17,7 +30,7 @@ impl RandomEvaluationFunction {
 
 impl EvaluationFunction for RandomEvaluationFunction {
     // The random evaluation function doesn't actually use the state
-    fn evaluate(&self, _state: &GameState) -> Vec<f64> {
+    fn evaluate(&self, _game: &Game) -> Vec<f64> {
         let mut rng = rand::thread_rng();
 
         // Generate n-1 random numbers between 0 and 1
@@ -36,3 +49,445 @@ impl EvaluationFunction for RandomEvaluationFunction {
         vector
     }
 }
+
+// Averages the score vectors of games played out from the position, so any spec gets a
+// heuristic for free. With probability epsilon a rollout plays a uniformly random move,
+// otherwise the move with the highest ordering hint from the spec.
+pub struct RolloutEvaluationFunction {
+    num_rollouts: usize,
+    // Rollouts still going after this many moves are scored as they stand
+    max_rollout_length: usize,
+    epsilon: f64,
+    rng: RefCell<StdRng>,
+}
+
+impl RolloutEvaluationFunction {
+    // Without a seed the rollouts differ from run to run
+    pub fn new(
+        num_rollouts: usize,
+        max_rollout_length: usize,
+        epsilon: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        RolloutEvaluationFunction {
+            num_rollouts,
+            max_rollout_length,
+            epsilon,
+            rng: RefCell::new(rng),
+        }
+    }
+
+    fn choose_move(&self, game: &Game, moves: &[Move], rng: &mut StdRng) -> Option<Move> {
+        if rng.gen::<f64>() < self.epsilon {
+            return moves.choose(rng).cloned();
+        }
+        let best_hint = moves
+            .iter()
+            .map(|game_move| game.get_move_ordering_hint(game_move))
+            .max()?;
+        let best_moves: Vec<&Move> = moves
+            .iter()
+            .filter(|game_move| game.get_move_ordering_hint(game_move) == best_hint)
+            .collect();
+        best_moves.choose(rng).map(|&game_move| game_move.clone())
+    }
+}
+
+impl EvaluationFunction for RolloutEvaluationFunction {
+    fn evaluate(&self, game: &Game) -> Vec<f64> {
+        let num_players = game.get_num_players();
+        // Scoring the game itself would call back into this function
+        if self.num_rollouts == 0 {
+            return vec![1.0 / num_players as f64; num_players];
+        }
+        let mut rng = self.rng.borrow_mut();
+        let mut score_sums = vec![0.0; num_players];
+        for _ in 0..self.num_rollouts {
+            let mut rollout = game.fork();
+            let mut rollout_length = 0;
+            while rollout_length < self.max_rollout_length
+                && rollout.get_game_status() == GameStatus::Ongoing
+            {
+                let moves = rollout.get_valid_moves();
+                match self.choose_move(&rollout, &moves, &mut rng) {
+                    Some(game_move) => rollout.transition(&game_move),
+                    None => break,
+                }
+                rollout_length += 1;
+            }
+            for (sum, value) in score_sums.iter_mut().zip(rollout.get_score()) {
+                *sum += value;
+            }
+        }
+        score_sums
+            .into_iter()
+            .map(|sum| sum / self.num_rollouts as f64)
+            .collect()
+    }
+}
+
+// Turns non-negative per-player values into shares that sum to 1
+fn normalize(values: Vec<f64>) -> Vec<f64> {
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return vec![1.0 / values.len() as f64; values.len()];
+    }
+    values.into_iter().map(|value| value / total).collect()
+}
+
+// Mixes disc parity, mobility, corners and stable edge discs. Mobility dominates the opening and
+// parity takes over as the board fills up.
+pub struct OthelloEvaluationFunction {
+    num_players: usize,
+}
+
+impl OthelloEvaluationFunction {
+    pub fn new(num_players: usize) -> Self {
+        OthelloEvaluationFunction { num_players }
+    }
+
+    // Discs connected to an owned corner along an edge can never be flipped
+    fn stable_edge_discs(board: &Board, num_players: usize) -> Vec<f64> {
+        let num_rows = board.get_num_rows();
+        let num_cols = board.get_num_cols();
+        let mut stable = Board::new(num_rows, num_cols);
+        if num_rows == 0 || num_cols == 0 {
+            return vec![0.0; num_players];
+        }
+        let corners = [
+            (0, 0, 1, 1),
+            (0, num_cols - 1, 1, -1),
+            (num_rows - 1, 0, -1, 1),
+            (num_rows - 1, num_cols - 1, -1, -1),
+        ];
+        for &(row, col, row_step, col_step) in &corners {
+            let Some(owner) = board[(row, col)].get_owner() else {
+                continue;
+            };
+            stable[(row, col)] = BoardCell::owned_by(owner);
+            for direction in [(row_step, 0), (0, col_step)] {
+                for (r, c, _) in board
+                    .ray(row, col, direction)
+                    .take_while(|(_, _, cell)| cell.is_owned_by(owner))
+                {
+                    stable[(r, c)] = BoardCell::owned_by(owner);
+                }
+            }
+        }
+
+        let mut counts = vec![0.0; num_players];
+        for (_, _, cell) in stable.cells() {
+            if let Some(owner) = cell.get_owner() {
+                counts[owner.to_usize()] += 1.0;
+            }
+        }
+        counts
+    }
+}
+
+impl EvaluationFunction for OthelloEvaluationFunction {
+    fn evaluate(&self, game: &Game) -> Vec<f64> {
+        let board = game.get_board();
+        let num_rows = board.get_num_rows();
+        let num_cols = board.get_num_cols();
+
+        let mut discs = vec![0.0; self.num_players];
+        let mut mobility = vec![0.0; self.num_players];
+        let mut corners = vec![0.0; self.num_players];
+        for (row, col, cell) in board.cells() {
+            match cell.get_owner() {
+                Some(owner) => {
+                    discs[owner.to_usize()] += 1.0;
+                    let is_corner =
+                        (row == 0 || row == num_rows - 1) && (col == 0 || col == num_cols - 1);
+                    if is_corner {
+                        corners[owner.to_usize()] += 1.0;
+                    }
+                }
+                None => {
+                    for (player_idx, moves) in mobility.iter_mut().enumerate() {
+                        if OthelloSpec::is_valid_move(board, row, col, Player(player_idx)) {
+                            *moves += 1.0;
+                        }
+                    }
+                }
+            }
+        }
+        let stable = Self::stable_edge_discs(board, self.num_players);
+
+        let filled = discs.iter().sum::<f64>() / (num_rows * num_cols).max(1) as f64;
+        let weighted_features = [
+            (0.15 + 0.5 * filled, normalize(discs)),
+            (0.35 * (1.0 - filled), normalize(mobility)),
+            (0.3, normalize(corners)),
+            (0.2, normalize(stable)),
+        ];
+        let total_weight: f64 = weighted_features.iter().map(|(weight, _)| weight).sum();
+        (0..self.num_players)
+            .map(|player_idx| {
+                weighted_features
+                    .iter()
+                    .map(|(weight, shares)| weight * shares[player_idx])
+                    .sum::<f64>()
+                    / total_weight
+            })
+            .collect()
+    }
+}
+
+// Counts the lines of n_in_a_row cells that only one player occupies, weighting each by how
+// many of its cells that player already holds
+pub struct PushUpFourEvaluationFunction {
+    num_players: usize,
+    n_in_a_row: usize,
+}
+
+impl PushUpFourEvaluationFunction {
+    pub fn new(num_players: usize, n_in_a_row: usize) -> Self {
+        PushUpFourEvaluationFunction {
+            num_players,
+            n_in_a_row,
+        }
+    }
+}
+
+impl EvaluationFunction for PushUpFourEvaluationFunction {
+    fn evaluate(&self, game: &Game) -> Vec<f64> {
+        let board = game.get_board();
+
+        // Every player starts with one so that a lack of threats doesn't zero out the vector
+        let mut threats = vec![1.0; self.num_players];
+        for line in board.lines(self.n_in_a_row) {
+            let mut owner = None;
+            let mut count = 0;
+            let mut contested = false;
+            for &position in &line {
+                if let Some(player) = board[position].get_owner() {
+                    if owner.is_some_and(|owner| owner != player) {
+                        contested = true;
+                        break;
+                    }
+                    owner = Some(player);
+                    count += 1;
+                }
+            }
+            if let (Some(owner), false) = (owner, contested) {
+                // Lines closer to completion are worth disproportionately more
+                threats[owner.to_usize()] += 4f64.powi(count - 1);
+            }
+        }
+        normalize(threats)
+    }
+}
+
+// Scores derived from the position's hash, so that searches can be compared in tests without
+// score ties making them pick different but equally good lines
+#[cfg(test)]
+pub struct HashEvaluationFunction;
+
+#[cfg(test)]
+impl EvaluationFunction for HashEvaluationFunction {
+    fn evaluate(&self, game: &Game) -> Vec<f64> {
+        let values = (0..game.get_num_players() as u64)
+            .map(|player| (mix(game.get_hash() ^ player) >> 11) as f64 + 1.0)
+            .collect();
+        normalize(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::config::GameConfig;
+    use crate::game::MoveDelta;
+    use crate::game_spec::GameSpec;
+    use crate::push_up_four::PushUpFourSpec;
+
+    // Players keep claiming the only cell, so the game never ends. Counts the moves played.
+    struct EndlessSpec {
+        moves_played: Rc<Cell<usize>>,
+    }
+
+    impl GameSpec for EndlessSpec {
+        fn get_initial_board(&self, _config: &GameConfig, _num_players: usize) -> Board {
+            Board::new(1, 1)
+        }
+
+        fn get_initial_to_move(&self) -> Player {
+            Player(0)
+        }
+
+        fn get_valid_moves(&self, _game: &Game) -> Vec<Move> {
+            vec![Move::place(0, 0)]
+        }
+
+        fn transition(&self, game: &Game, _game_move: &Move) -> MoveDelta {
+            self.moves_played.set(self.moves_played.get() + 1);
+            MoveDelta {
+                changed_cells: vec![(0, 0, BoardCell::owned_by(game.get_to_move()))],
+                next_to_move: game.get_next_player(),
+            }
+        }
+
+        fn is_win(&self, _game: &Game, _player: Player) -> bool {
+            false
+        }
+
+        fn is_tie(&self, _game: &Game) -> bool {
+            false
+        }
+    }
+
+    // Two cells, and whoever claims the right one wins. The hint points at it.
+    struct RaceSpec;
+
+    impl GameSpec for RaceSpec {
+        fn get_initial_board(&self, _config: &GameConfig, _num_players: usize) -> Board {
+            Board::new(1, 2)
+        }
+
+        fn get_initial_to_move(&self) -> Player {
+            Player(0)
+        }
+
+        fn get_valid_moves(&self, game: &Game) -> Vec<Move> {
+            game.get_board()
+                .cells()
+                .filter(|(_, _, cell)| cell.is_empty())
+                .map(|(row, col, _)| Move::place(row, col))
+                .collect()
+        }
+
+        fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta {
+            let (row, col) = game_move.get_destination().unwrap();
+            MoveDelta {
+                changed_cells: vec![(row, col, BoardCell::owned_by(game.get_to_move()))],
+                next_to_move: game.get_next_player(),
+            }
+        }
+
+        fn is_win(&self, game: &Game, player: Player) -> bool {
+            game.get_board()[(0, 1)].is_owned_by(player)
+        }
+
+        fn is_tie(&self, _game: &Game) -> bool {
+            false
+        }
+
+        fn get_move_ordering_hint(&self, _game: &Game, game_move: &Move) -> i32 {
+            (game_move.get_destination() == Some((0, 1))) as i32
+        }
+    }
+
+    fn new_game(spec: Box<dyn GameSpec>, num_players: usize) -> Game {
+        Game::new(spec, num_players, &GameConfig::default()).unwrap()
+    }
+
+    // The spec's own evaluation function applied to the game with some cells filled in
+    fn evaluate_with(game: &Game, cells: &[(usize, usize, usize)]) -> Vec<f64> {
+        let mut game = game.fork();
+        let cells = cells
+            .iter()
+            .map(|&(row, col, player)| (row, col, BoardCell::owned_by(Player(player))))
+            .collect();
+        game.set_cells(cells);
+        game.create_evaluation_function().evaluate(&game)
+    }
+
+    fn rollout_score(num_rollouts: usize) -> Vec<f64> {
+        let mut game = Game::new(Box::new(PushUpFourSpec), 3, &GameConfig::default()).unwrap();
+        game.set_evaluation_function(Some(Box::new(RolloutEvaluationFunction::new(
+            num_rollouts,
+            100,
+            1.0,
+            Some(7),
+        ))));
+        game.get_score()
+    }
+
+    #[test]
+    fn rollouts_average_to_a_score_vector() {
+        let score = rollout_score(16);
+        assert_eq!(score.len(), 3);
+        assert!((score.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_rollouts_scores_the_game_as_even() {
+        assert_eq!(rollout_score(0), vec![1.0 / 3.0; 3]);
+    }
+
+    #[test]
+    fn rollouts_with_the_same_seed_agree() {
+        let mut game = new_game(Box::new(PushUpFourSpec), 3);
+        game.make_move(&game.get_valid_moves()[3].clone()).unwrap();
+        let score = || RolloutEvaluationFunction::new(16, 100, 1.0, Some(11)).evaluate(&game);
+        assert_eq!(score(), score());
+    }
+
+    #[test]
+    fn rollouts_stop_at_the_length_cap() {
+        let moves_played = Rc::new(Cell::new(0));
+        let spec = EndlessSpec {
+            moves_played: Rc::clone(&moves_played),
+        };
+        let game = new_game(Box::new(spec), 2);
+        let score = RolloutEvaluationFunction::new(3, 50, 1.0, Some(7)).evaluate(&game);
+        assert_eq!(moves_played.get(), 3 * 50);
+        // Unfinished games are scored as even
+        assert_eq!(score, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn greedy_rollouts_follow_the_ordering_hint() {
+        let game = new_game(Box::new(RaceSpec), 2);
+        let score = RolloutEvaluationFunction::new(16, 10, 0.0, Some(7)).evaluate(&game);
+        assert_eq!(score, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn heuristic_scores_sum_to_one() {
+        for num_players in 2..=4 {
+            for spec in [
+                Box::new(OthelloSpec) as Box<dyn GameSpec>,
+                Box::new(PushUpFourSpec),
+            ] {
+                let game = new_game(spec, num_players);
+                let score = evaluate_with(&game, &[(0, 0, 1)]);
+                assert_eq!(score.len(), num_players);
+                assert!((score.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn owning_a_corner_raises_an_othello_share() {
+        let game = new_game(Box::new(OthelloSpec), 2);
+        // The same disc further along the edge changes neither side's mobility
+        let on_edge = evaluate_with(&game, &[(0, 2, 0)]);
+        let in_corner = evaluate_with(&game, &[(0, 0, 0)]);
+        assert!(in_corner[0] > on_edge[0]);
+    }
+
+    #[test]
+    fn an_open_three_raises_a_push_up_four_share() {
+        let game = new_game(Box::new(PushUpFourSpec), 2);
+        let opponent = [(5, 6, 1), (4, 6, 1), (5, 0, 1)];
+        let scattered = evaluate_with(
+            &game,
+            &[&opponent[..], &[(5, 1, 0), (3, 3, 0), (5, 4, 0)]].concat(),
+        );
+        let open_three = evaluate_with(
+            &game,
+            &[&opponent[..], &[(5, 2, 0), (5, 3, 0), (5, 4, 0)]].concat(),
+        );
+        assert!(open_three[0] > scattered[0]);
+    }
+}
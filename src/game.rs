@@ -1,31 +1,47 @@
-use crate::game_elements::{BoardCell, GameStatus, Player};
-use crate::game_spec::GameSpec;
+use std::rc::Rc;
 
-#[derive(Clone)]
+use crate::board::Board;
+use crate::config::GameConfig;
+use crate::error::GameError;
+use crate::eval::EvaluationFunction;
+use crate::game_elements::{BoardCell, GameStatus, Move, Player, MAX_PLAYERS};
+use crate::game_spec::{GameSpec, PassPolicy};
+use crate::metadata::GameMetadata;
+use crate::zobrist::ZobristKeys;
+
+#[derive(Clone, PartialEq)]
 pub struct GameState {
     to_move: Player,
     move_num: usize,
-    board: Vec<Vec<BoardCell>>,
-    prev_state: Option<Box<GameState>>,
+    board: Board,
+    // Zobrist hash of to_move and board, maintained by Game
+    hash: u64,
 }
+impl Eq for GameState {}
 
-impl PartialEq for GameState {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_move == other.to_move &&
-        self.move_num == other.move_num &&
-        self.board == other.board
-        // Note that we do not compare prev_state
-    }
+// The effect of a move as reported by the spec: the new contents of every cell it changes,
+// and the player to move afterwards
+pub struct MoveDelta {
+    pub changed_cells: Vec<(usize, usize, BoardCell)>,
+    pub next_to_move: Player,
+}
+
+// Everything a move overwrote, so that it can be taken back in place
+struct UndoRecord {
+    // Previous contents of the changed cells, in the order they were changed
+    changed_cells: Vec<(usize, usize, BoardCell)>,
+    to_move: Player,
+    move_num: usize,
+    hash: u64,
 }
-impl Eq for GameState {}
 
 impl GameState {
-    pub fn new(to_move: Player, move_num: usize, board: Vec<Vec<BoardCell>>) -> Self {
+    pub fn new(to_move: Player, move_num: usize, board: Board) -> Self {
         GameState {
             to_move,
             move_num,
             board,
-            prev_state: None,
+            hash: 0,
         }
     }
 
@@ -37,57 +53,108 @@ impl GameState {
         self.move_num
     }
 
-    pub fn get_board(&self) -> &Vec<Vec<BoardCell>> {
+    pub fn get_board(&self) -> &Board {
         &self.board
     }
 
-    pub fn get_prev_state(&self) -> &Option<Box<GameState>> {
-        &self.prev_state
-    }
-
-    pub fn set_prev_state(&mut self, state: Box<GameState>) {
-        self.prev_state = Some(state);
+    pub fn get_hash(&self) -> u64 {
+        self.hash
     }
 }
 
 pub struct Game {
-    state: Box<GameState>,
-    spec: Box<dyn GameSpec>,
+    state: GameState,
+    undo_stack: Vec<UndoRecord>,
+    // Moves made through make_move, and the ones taken back with undo_move that can be redone,
+    // most recent last. Searches transition without touching these.
+    played_moves: Vec<Move>,
+    undone_moves: Vec<Move>,
+    // Shared with the games forked from this one
+    spec: Rc<dyn GameSpec>,
     num_players: usize,
+    zobrist: Rc<ZobristKeys>,
+    // Estimates the score of ongoing games; without one they are scored as even
+    eval_function: Option<Box<dyn EvaluationFunction>>,
+    // With the spec's defaults filled in
+    config: Rc<GameConfig>,
 }
 
 impl Game {
+    // Fails if the config doesn't fit the spec's schema or the game can't be played by that
+    // many players
     pub fn new(
         spec: Box<dyn GameSpec>,
         num_players: usize,
-    ) -> Game {
-        Game {
-            state: Box::new(GameState::new(
-                spec.get_initial_to_move(),
-                0,
-                spec.get_initial_board(),
-            )),
-            spec,
+        config: &GameConfig,
+    ) -> Result<Game, GameError> {
+        let config = config
+            .resolve(&spec.get_config_schema())
+            .map_err(GameError::InvalidConfig)?;
+        let metadata = spec.get_metadata(&config);
+        let min = metadata.min_players.max(1);
+        let max = metadata.max_players.min(MAX_PLAYERS);
+        if num_players < min || num_players > max {
+            return Err(GameError::BadPlayerCount {
+                num_players,
+                min,
+                max,
+            });
+        }
+        let initial_board = spec.get_initial_board(&config, num_players);
+        let mut state = GameState::new(spec.get_initial_to_move(), 0, initial_board);
+        let mut zobrist = ZobristKeys::new(
+            state.board.get_num_rows(),
+            state.board.get_num_cols(),
             num_players,
+        );
+        if spec.hash_includes_move_num() {
+            zobrist = zobrist.with_move_num();
+        }
+        state.hash = zobrist.hash_state(&state);
+        Ok(Game {
+            state,
+            undo_stack: Vec::new(),
+            played_moves: Vec::new(),
+            undone_moves: Vec::new(),
+            spec: Rc::from(spec),
+            num_players,
+            zobrist: Rc::new(zobrist),
+            eval_function: None,
+            config: Rc::new(config),
+        })
+    }
+
+    // A copy of the current position without its history, to play ahead on without
+    // touching this game. Moves made before the fork can't be undone in the copy, and the copy
+    // has no evaluation function, so evaluators can score their forks without recursing.
+    pub fn fork(&self) -> Game {
+        Game {
+            state: self.state.clone(),
+            undo_stack: Vec::new(),
+            played_moves: Vec::new(),
+            undone_moves: Vec::new(),
+            spec: Rc::clone(&self.spec),
+            num_players: self.num_players,
+            zobrist: Rc::clone(&self.zobrist),
+            eval_function: None,
+            config: Rc::clone(&self.config),
         }
     }
 
     pub fn get_game_status(&self) -> GameStatus {
+        if let Some(outcome) = self.spec.get_outcome(self) {
+            return GameStatus::Over(outcome);
+        }
         if self.is_tie() {
             return GameStatus::Tie;
         }
         for player_idx in 0..self.num_players {
-            if self.is_win(Player::from(player_idx)) {
-                return match player_idx {
-                    0 => GameStatus::Player0Win,
-                    1 => GameStatus::Player1Win,
-                    2 => GameStatus::Player2Win,
-                    3 => GameStatus::Player3Win,
-                    _ => panic!("Invalid player number"),
-                };
+            let player = Player(player_idx);
+            if self.is_win(player) {
+                return GameStatus::Win(player);
             }
         }
-        return GameStatus::Ongoing;
+        GameStatus::Ongoing
     }
 
     fn create_score_vector(&self, winning_player: Player) -> Vec<f64> {
@@ -97,28 +164,81 @@ impl Game {
         score
     }
 
-    // TODO: gracefully apply an injected eval function to the state (for ongoing game)
+    pub fn set_evaluation_function(&mut self, eval_function: Option<Box<dyn EvaluationFunction>>) {
+        self.eval_function = eval_function;
+    }
+
+    // The outcome of finished games, or the evaluation function's estimate for ongoing ones
     pub fn get_score(&self) -> Vec<f64> {
         match self.get_game_status() {
-            GameStatus::Tie | GameStatus::Ongoing => {
-                vec![1.0 / self.num_players as f64; self.num_players]
-            }
-            GameStatus::Player0Win => self.create_score_vector(Player::Player0),
-            GameStatus::Player1Win => self.create_score_vector(Player::Player1),
-            GameStatus::Player2Win => self.create_score_vector(Player::Player2),
-            GameStatus::Player3Win => self.create_score_vector(Player::Player3),
+            GameStatus::Ongoing => match &self.eval_function {
+                Some(eval_function) => eval_function.evaluate(self),
+                None => vec![1.0 / self.num_players as f64; self.num_players],
+            },
+            GameStatus::Tie => vec![1.0 / self.num_players as f64; self.num_players],
+            GameStatus::Win(player) => self.create_score_vector(player),
+            GameStatus::Over(outcome) => outcome.to_score(),
+        }
+    }
+
+    // Checked version of transition for moves that come from outside the searches, which only
+    // play moves they got from get_valid_moves
+    pub fn make_move(&mut self, game_move: &Move) -> Result<(), GameError> {
+        if self.get_game_status() != GameStatus::Ongoing {
+            return Err(GameError::GameOver);
         }
+        if !self.get_valid_moves().contains(game_move) {
+            return Err(GameError::IllegalMove(game_move.clone()));
+        }
+        self.transition(game_move);
+        self.played_moves.push(game_move.clone());
+        // A new move starts a new line of play
+        self.undone_moves.clear();
+        Ok(())
+    }
+
+    // Takes back the last move made through make_move, which can then be redone
+    pub fn undo_move(&mut self) -> Result<(), GameError> {
+        let game_move = self.played_moves.pop().ok_or(GameError::NothingToUndo)?;
+        self.undo_transition();
+        self.undone_moves.push(game_move);
+        Ok(())
+    }
+
+    // Replays the last move taken back with undo_move
+    pub fn redo_move(&mut self) -> Result<(), GameError> {
+        let game_move = self.undone_moves.pop().ok_or(GameError::NothingToRedo)?;
+        self.transition(&game_move);
+        self.played_moves.push(game_move);
+        Ok(())
     }
 
-     pub fn undo_transition(&mut self) {
-        if let Some(prev_state) = self.state.prev_state.take() {
-            self.state = prev_state;
-        } else {
+    pub fn can_undo(&self) -> bool {
+        !self.played_moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone_moves.is_empty()
+    }
+
+    // Searches undo exactly the transitions they made, so an empty stack here is a bug. Moves
+    // from outside are taken back with undo_move, which checks that there is one.
+    pub(crate) fn undo_transition(&mut self) {
+        let Some(undo_record) = self.undo_stack.pop() else {
             panic!("Attempted to undo initial state");
+        };
+        // Restore in reverse in case the spec changed a cell more than once
+        for (row, col, cell) in undo_record.changed_cells.into_iter().rev() {
+            self.state.board[(row, col)] = cell;
         }
+        self.state.to_move = undo_record.to_move;
+        self.state.move_num = undo_record.move_num;
+        self.state.hash = undo_record.hash;
     }
 
-    pub fn get_state(&self) -> &Box<GameState> {
+    // Not needed by the bundled specs, but generated ones read the board through the state
+    #[allow(dead_code)]
+    pub fn get_state(&self) -> &GameState {
         &self.state
     }
 
@@ -126,9 +246,18 @@ impl Game {
         self.num_players
     }
 
+    // Checks a player number from outside, e.g. from JS, against the players in the game
+    pub fn get_player(&self, player_idx: usize) -> Result<Player, GameError> {
+        let player = Player::try_from(player_idx)?;
+        if player_idx >= self.num_players {
+            return Err(GameError::InvalidPlayer(player_idx));
+        }
+        Ok(player)
+    }
+
     pub fn get_next_player(&self) -> Player {
         let next_player_num = (self.get_to_move().to_usize() + 1) % self.get_num_players();
-        Player::from(next_player_num)
+        Player(next_player_num)
     }
 
     pub fn get_to_move(&self) -> Player {
@@ -139,32 +268,113 @@ impl Game {
         self.state.get_move_num()
     }
 
-    pub fn get_board(&self) -> &Vec<Vec<BoardCell>> {
+    pub fn get_board(&self) -> &Board {
         self.state.get_board()
     }
 
-    pub fn get_prev_state(&self) -> &Option<Box<GameState>> {
-        self.state.get_prev_state()
+    pub fn get_hash(&self) -> u64 {
+        self.state.get_hash()
+    }
+
+    pub fn get_config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn get_num_rows(&self) -> usize {
+        self.get_board().get_num_rows()
+    }
+
+    pub fn get_num_cols(&self) -> usize {
+        self.get_board().get_num_cols()
     }
 
     /*
     ----------The functions below call the functions in the game spec----------
     */
 
-    pub fn get_valid_moves(&self) -> Vec<(usize, usize)> {
-        self.spec.get_valid_moves(self)
+    // The spec's moves, plus a pass if its pass policy allows one
+    pub fn get_valid_moves(&self) -> Vec<Move> {
+        let mut moves = self.spec.get_valid_moves(self);
+        let can_pass = match self.spec.get_pass_policy() {
+            PassPolicy::Never => false,
+            PassPolicy::WhenForced => moves.is_empty(),
+            PassPolicy::Always => true,
+        };
+        if can_pass {
+            moves.push(Move::Pass);
+        }
+        moves
     }
 
-    pub fn transition(&mut self, move_row: usize, move_col: usize) {
-        // Other state is (initially) the state after the move is made
-        let mut other_state = self.spec.transition(self, move_row, move_col);
+    pub fn transition(&mut self, game_move: &Move) {
+        let delta = match game_move {
+            Move::Play { .. } => self.spec.transition(self, game_move),
+            Move::Pass => MoveDelta {
+                changed_cells: Vec::new(),
+                next_to_move: self.get_next_player(),
+            },
+        };
+        self.apply_delta(delta, self.get_move_num() + 1);
+    }
 
-        // We use std::mem::swap to exchange self.state with other_state directly
-        std::mem::swap(&mut self.state, &mut other_state);
+    // Hands the turn to another player without changing the board, e.g. for searches that let
+    // opponents reply out of turn. It is undone with undo_transition like a regular move.
+    pub fn set_to_move(&mut self, player: Player) {
+        let delta = MoveDelta {
+            changed_cells: Vec::new(),
+            next_to_move: player,
+        };
+        self.apply_delta(delta, self.get_move_num());
+    }
+
+    // Puts cells on the board directly, for tests that start from positions no game reaches
+    #[cfg(test)]
+    pub fn set_cells(&mut self, changed_cells: Vec<(usize, usize, BoardCell)>) {
+        let delta = MoveDelta {
+            changed_cells,
+            next_to_move: self.get_to_move(),
+        };
+        self.apply_delta(delta, self.get_move_num());
+    }
+
+    // Changes the state in place, updating the hash along the way, and records how to undo it
+    fn apply_delta(&mut self, delta: MoveDelta, move_num: usize) {
+        let mut undo_record = UndoRecord {
+            changed_cells: Vec::with_capacity(delta.changed_cells.len()),
+            to_move: self.state.to_move,
+            move_num: self.state.move_num,
+            hash: self.state.hash,
+        };
+        let mut hash = self.state.hash
+            ^ self.zobrist.to_move_key(self.state.to_move)
+            ^ self.zobrist.to_move_key(delta.next_to_move)
+            ^ self.zobrist.move_num_key(self.state.move_num)
+            ^ self.zobrist.move_num_key(move_num);
+        for (row, col, cell) in delta.changed_cells {
+            hash ^= self.zobrist.cell_key(row, col, &cell);
+            let old_cell = self.state.board.set(row, col, cell);
+            hash ^= self.zobrist.cell_key(row, col, &old_cell);
+            undo_record.changed_cells.push((row, col, old_cell));
+        }
+        self.state.to_move = delta.next_to_move;
+        self.state.move_num = move_num;
+        self.state.hash = hash;
+        self.undo_stack.push(undo_record);
+    }
+
+    pub fn get_metadata(&self) -> GameMetadata {
+        self.spec.get_metadata(&self.config)
+    }
+
+    pub fn create_evaluation_function(&self) -> Box<dyn EvaluationFunction> {
+        self.spec.get_evaluation_function(self)
+    }
 
-        // After swapping, other_state contains the previous state
-        // We now set the previous state of self.state to be other_state
-        self.state.set_prev_state(other_state);
+    pub fn get_move_ordering_hint(&self, game_move: &Move) -> i32 {
+        match game_move {
+            Move::Play { .. } => self.spec.get_move_ordering_hint(self, game_move),
+            Move::Pass => 0,
+        }
     }
 
     pub fn is_win(&self, player: Player) -> bool {
@@ -175,3 +385,230 @@ impl Game {
         self.spec.is_tie(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::othello::OthelloSpec;
+    use crate::push_up_four::PushUpFourSpec;
+
+    // Players fill a row of three cells and the game never ends, so moves can be tested on a
+    // full board
+    struct RowSpec {
+        pass_policy: PassPolicy,
+    }
+
+    impl GameSpec for RowSpec {
+        fn get_initial_board(&self, _config: &GameConfig, _num_players: usize) -> Board {
+            Board::new(1, 3)
+        }
+
+        fn get_initial_to_move(&self) -> Player {
+            Player(0)
+        }
+
+        fn get_valid_moves(&self, game: &Game) -> Vec<Move> {
+            game.get_board()
+                .cells()
+                .filter(|(_, _, cell)| cell.is_empty())
+                .map(|(row, col, _)| Move::place(row, col))
+                .collect()
+        }
+
+        fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta {
+            let (row, col) = game_move.get_destination().unwrap();
+            MoveDelta {
+                changed_cells: vec![(row, col, BoardCell::owned_by(game.get_to_move()))],
+                next_to_move: game.get_next_player(),
+            }
+        }
+
+        fn is_win(&self, _game: &Game, _player: Player) -> bool {
+            false
+        }
+
+        fn is_tie(&self, _game: &Game) -> bool {
+            false
+        }
+
+        fn get_pass_policy(&self) -> PassPolicy {
+            self.pass_policy
+        }
+    }
+
+    fn new_row_game(pass_policy: PassPolicy) -> Game {
+        new_game(Box::new(RowSpec { pass_policy }), 2)
+    }
+
+    // Whether a pass is offered before and after the row is filled
+    fn offers_pass(pass_policy: PassPolicy) -> (bool, bool) {
+        let mut game = new_row_game(pass_policy);
+        let before = game.get_valid_moves().contains(&Move::Pass);
+        for col in 0..3 {
+            game.make_move(&Move::place(0, col)).unwrap();
+        }
+        let after = game.get_valid_moves().contains(&Move::Pass);
+        (before, after)
+    }
+
+    fn new_game(spec: Box<dyn GameSpec>, num_players: usize) -> Game {
+        Game::new(spec, num_players, &GameConfig::default()).unwrap()
+    }
+
+    // Plays the move at a fixed offset into the valid moves until the game ends
+    fn play_out(game: &mut Game, offset: usize) -> Vec<GameState> {
+        let mut states = vec![game.state.clone()];
+        while game.get_game_status() == GameStatus::Ongoing {
+            let moves = game.get_valid_moves();
+            if moves.is_empty() {
+                break;
+            }
+            game.transition(&moves[(states.len() * offset) % moves.len()]);
+            assert_eq!(game.get_hash(), game.zobrist.hash_state(&game.state));
+            states.push(game.state.clone());
+        }
+        states
+    }
+
+    #[test]
+    fn undo_transition_restores_every_state() {
+        let specs: [fn() -> Box<dyn GameSpec>; 2] =
+            [|| Box::new(OthelloSpec), || Box::new(PushUpFourSpec)];
+        for create_spec in specs {
+            for offset in [1, 3, 5] {
+                let mut game = new_game(create_spec(), 2);
+                let mut states = play_out(&mut game, offset);
+                states.pop();
+                while let Some(state) = states.pop() {
+                    game.undo_transition();
+                    assert!(game.state == state);
+                }
+                assert!(game.undo_stack.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn pass_policies_decide_when_passes_are_offered() {
+        assert_eq!(offers_pass(PassPolicy::Never), (false, false));
+        assert_eq!(offers_pass(PassPolicy::WhenForced), (false, true));
+        assert_eq!(offers_pass(PassPolicy::Always), (true, true));
+    }
+
+    #[test]
+    fn passing_only_hands_over_the_turn() {
+        let mut game = new_row_game(PassPolicy::Always);
+        game.make_move(&Move::place(0, 1)).unwrap();
+        let board = game.get_board().clone();
+        let hash = game.get_hash();
+
+        game.make_move(&Move::Pass).unwrap();
+        assert!(*game.get_board() == board);
+        assert_eq!(game.get_to_move(), Player(0));
+        assert_eq!(game.get_move_num(), 2);
+
+        game.undo_move().unwrap();
+        assert_eq!(game.get_to_move(), Player(1));
+        assert_eq!(game.get_hash(), hash);
+    }
+
+    #[test]
+    fn passing_is_illegal_when_the_policy_forbids_it() {
+        let mut game = new_row_game(PassPolicy::WhenForced);
+        assert_eq!(
+            game.make_move(&Move::Pass),
+            Err(GameError::IllegalMove(Move::Pass))
+        );
+    }
+
+    #[test]
+    fn new_rejects_player_counts_and_configs_the_spec_doesnt_support() {
+        let create = |num_players, config: &GameConfig| {
+            Game::new(Box::new(PushUpFourSpec), num_players, config).err()
+        };
+        let bad_player_count = |num_players| GameError::BadPlayerCount {
+            num_players,
+            min: 2,
+            max: MAX_PLAYERS,
+        };
+        assert_eq!(create(1, &GameConfig::default()), Some(bad_player_count(1)));
+        assert_eq!(
+            create(MAX_PLAYERS + 1, &GameConfig::default()),
+            Some(bad_player_count(MAX_PLAYERS + 1))
+        );
+        let too_wide = GameConfig {
+            num_cols: Some(100),
+            ..GameConfig::default()
+        };
+        assert!(matches!(
+            create(2, &too_wide),
+            Some(GameError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn player_numbers_from_outside_are_checked() {
+        assert_eq!(Player::try_from(3), Ok(Player(3)));
+        assert_eq!(
+            Player::try_from(MAX_PLAYERS),
+            Err(GameError::InvalidPlayer(MAX_PLAYERS))
+        );
+        let game = new_game(Box::new(PushUpFourSpec), 3);
+        assert_eq!(game.get_player(2), Ok(Player(2)));
+        assert_eq!(game.get_player(3), Err(GameError::InvalidPlayer(3)));
+    }
+
+    #[test]
+    fn make_move_rejects_illegal_moves_and_finished_games() {
+        let mut game = new_row_game(PassPolicy::Never);
+        game.make_move(&Move::place(0, 0)).unwrap();
+        assert_eq!(
+            game.make_move(&Move::place(0, 0)),
+            Err(GameError::IllegalMove(Move::place(0, 0)))
+        );
+        assert_eq!(game.get_move_num(), 1);
+
+        let mut game = new_game(Box::new(PushUpFourSpec), 2);
+        for col in [0, 1, 0, 1, 0, 1, 0] {
+            game.make_move(&Move::place(5, col)).unwrap();
+        }
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(0)));
+        assert_eq!(game.make_move(&Move::place(5, 3)), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut game = new_game(Box::new(OthelloSpec), 2);
+        let mut states = vec![game.state.clone()];
+        for _ in 0..6 {
+            let game_move = game.get_valid_moves()[0].clone();
+            game.make_move(&game_move).unwrap();
+            states.push(game.state.clone());
+        }
+
+        for state in states.iter().rev().skip(1) {
+            game.undo_move().unwrap();
+            assert!(game.state == *state);
+        }
+        assert!(!game.can_undo());
+        assert_eq!(game.undo_move(), Err(GameError::NothingToUndo));
+
+        for state in states.iter().skip(1) {
+            game.redo_move().unwrap();
+            assert!(game.state == *state);
+        }
+        assert!(!game.can_redo());
+        assert_eq!(game.redo_move(), Err(GameError::NothingToRedo));
+    }
+
+    #[test]
+    fn a_new_move_clears_the_redo_history() {
+        let mut game = new_row_game(PassPolicy::Never);
+        game.make_move(&Move::place(0, 0)).unwrap();
+        game.undo_move().unwrap();
+        assert!(game.can_redo());
+        game.make_move(&Move::place(0, 2)).unwrap();
+        assert!(!game.can_redo());
+        assert_eq!(game.redo_move(), Err(GameError::NothingToRedo));
+    }
+}
@@ -1,48 +1,283 @@
+use std::cmp::Ordering;
 use std::hash::Hash;
 
-use wasm_bindgen::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Copy, Clone, Hash)]
-#[wasm_bindgen]
-pub enum Player {
-    Player0,
-    Player1,
-    Player2,
-    Player3,
-}
+use crate::error::GameError;
+
+// Upper bound on the number of players in a game
+pub const MAX_PLAYERS: usize = 8;
+
+// Index of a player, from 0 to MAX_PLAYERS - 1. Player 0 moves first in most games. Specs
+// create players directly, while numbers from outside go through try_from.
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug, Serialize)]
+#[serde(transparent)]
+pub struct Player(pub usize);
+
+impl TryFrom<usize> for Player {
+    type Error = GameError;
 
-impl From<usize> for Player {
-    fn from(num: usize) -> Self {
-        match num {
-            0 => Player::Player0,
-            1 => Player::Player1,
-            2 => Player::Player2,
-            3 => Player::Player3,
-            _ => panic!("Invalid player number"),
+    fn try_from(num: usize) -> Result<Self, GameError> {
+        if num >= MAX_PLAYERS {
+            return Err(GameError::InvalidPlayer(num));
         }
+        Ok(Player(num))
     }
 }
 
 impl Player {
-    pub fn to_usize(&self) -> usize {
-        match self {
-            Player::Player0 => 0,
-            Player::Player1 => 1,
-            Player::Player2 => 2,
-            Player::Player3 => 3,
-        }
+    pub fn to_usize(self) -> usize {
+        self.0
     }
 }
 
-#[derive(PartialEq)]
-#[wasm_bindgen]
+// Serialized for JS as "Ongoing", "Tie", { "Win": player } or { "Over": outcome }
+#[derive(PartialEq, Clone, Debug, Serialize)]
 pub enum GameStatus {
-    Player0Win,
-    Player1Win,
-    Player2Win,
-    Player3Win,
+    Win(Player),
     Ongoing,
     Tie,
+    // Any other result, as reported by the spec
+    Over(Outcome),
+}
+
+// How a finished game turned out for each player, indexed by player. Not every kind is used by
+// the bundled specs.
+#[allow(dead_code)]
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub enum Outcome {
+    // Finishing position, 0 being first. Players who drew share a position.
+    Placements(Vec<usize>),
+    // Final point totals
+    Points(Vec<f64>),
+    // How much each player values the result; scaled to sum to 1 when scored
+    Utilities(Vec<f64>),
+}
+
+impl Outcome {
+    // Converts the outcome into a score vector that sums to 1, as the searches expect
+    pub fn to_score(&self) -> Vec<f64> {
+        let values: Vec<f64> = match self {
+            // Each player earns a point for every player they finished ahead of, and half a
+            // point for every player they drew with
+            Outcome::Placements(placements) => placements
+                .iter()
+                .map(|&place| {
+                    placements
+                        .iter()
+                        .map(|&other| match other.cmp(&place) {
+                            Ordering::Greater => 1.0,
+                            Ordering::Equal => 0.5,
+                            Ordering::Less => 0.0,
+                        })
+                        .sum::<f64>()
+                        - 0.5
+                })
+                .collect(),
+            // Only the points above the lowest total count, so the last player scores nothing
+            Outcome::Points(points) => {
+                let lowest = points.iter().copied().fold(f64::INFINITY, f64::min);
+                points.iter().map(|&value| value - lowest).collect()
+            }
+            Outcome::Utilities(utilities) => {
+                utilities.iter().map(|&value| value.max(0.0)).collect()
+            }
+        };
+        let total: f64 = values.iter().sum();
+        if total <= 0.0 {
+            return vec![1.0 / values.len() as f64; values.len()];
+        }
+        values.into_iter().map(|value| value / total).collect()
+    }
+}
+
+// Kind of piece, numbered by the spec, for games with more than one (e.g. men and kings).
+// Games with a single kind use 0.
+pub type PieceKind = usize;
+
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug, Serialize)]
+pub struct Piece {
+    // None for neutral pieces that belong to no player
+    pub owner: Option<Player>,
+    pub kind: PieceKind,
+}
+
+// Serialized for JS as "Empty", "Blocked", { "Piece": piece } or { "Stack": [piece, ...] }.
+// Not every kind of cell is used by the bundled specs.
+#[allow(dead_code)]
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Serialize, Default)]
+pub enum BoardCell {
+    #[default]
+    Empty,
+    // Not part of the playing area, such as a wall or a hole; nothing can be put here
+    Blocked,
+    Piece(Piece),
+    // Two or more pieces on top of each other, bottom first. Use from_pieces to build cells so
+    // that a single piece is never stored as a stack.
+    Stack(Vec<Piece>),
 }
 
-pub type BoardCell = Option<Player>;
+impl BoardCell {
+    // A cell holding a piece of the default kind, as in most games
+    pub fn owned_by(player: Player) -> BoardCell {
+        BoardCell::Piece(Piece {
+            owner: Some(player),
+            kind: 0,
+        })
+    }
+
+    // Builds the cell holding the given pieces, bottom first
+    #[allow(dead_code)]
+    pub fn from_pieces(mut pieces: Vec<Piece>) -> BoardCell {
+        match pieces.len() {
+            0 => BoardCell::Empty,
+            1 => BoardCell::Piece(pieces.pop().unwrap()),
+            _ => BoardCell::Stack(pieces),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == BoardCell::Empty
+    }
+
+    // The pieces in the cell, bottom first; none for empty and blocked cells
+    pub fn get_pieces(&self) -> &[Piece] {
+        match self {
+            BoardCell::Empty | BoardCell::Blocked => &[],
+            BoardCell::Piece(piece) => std::slice::from_ref(piece),
+            BoardCell::Stack(pieces) => pieces,
+        }
+    }
+
+    // The piece on top, which is the one that controls the cell in stacking games
+    pub fn get_top(&self) -> Option<Piece> {
+        self.get_pieces().last().copied()
+    }
+
+    pub fn get_owner(&self) -> Option<Player> {
+        self.get_top().and_then(|piece| piece.owner)
+    }
+
+    pub fn is_owned_by(&self, player: Player) -> bool {
+        self.get_owner() == Some(player)
+    }
+}
+
+// A move as the spec defines it. The searches never look inside one; they only compare, hash
+// and replay moves. Serialized for JS as
+// { "Play": { "from": [row, col] or null, "to": [row, col], "piece": kind or null, "path": [...] } }
+// or "Pass".
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Serialize, Deserialize)]
+pub enum Move {
+    Play {
+        // None when the piece is placed from off the board
+        from: Option<(usize, usize)>,
+        to: (usize, usize),
+        // The piece placed or moved, for games where the player chooses one
+        piece: Option<PieceKind>,
+        // Cells passed through on the way from `from` to `to`, e.g. for chained jumps
+        path: Vec<(usize, usize)>,
+    },
+    // Hands the turn to the next player without changing the board
+    Pass,
+}
+
+impl Move {
+    // Puts a piece on an empty cell, as in most placement games
+    pub fn place(row: usize, col: usize) -> Move {
+        Move::Play {
+            from: None,
+            to: (row, col),
+            piece: None,
+            path: Vec::new(),
+        }
+    }
+
+    // Moves the piece at `from` straight to `to`. None of the bundled specs move pieces yet.
+    #[allow(dead_code)]
+    pub fn step(from: (usize, usize), to: (usize, usize)) -> Move {
+        Move::Play {
+            from: Some(from),
+            to,
+            piece: None,
+            path: Vec::new(),
+        }
+    }
+
+    // The cell the move ends on, or None for a pass
+    pub fn get_destination(&self) -> Option<(usize, usize)> {
+        match self {
+            Move::Play { to, .. } => Some(*to),
+            Move::Pass => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_scores(outcome: Outcome, expected: &[f64]) {
+        let score = outcome.to_score();
+        assert_eq!(score.len(), expected.len());
+        for (value, expected) in score.iter().zip(expected) {
+            assert!(
+                (value - expected).abs() < 1e-9,
+                "{:?} != {:?}",
+                score,
+                expected
+            );
+        }
+    }
+
+    fn piece(owner: usize, kind: PieceKind) -> Piece {
+        Piece {
+            owner: Some(Player(owner)),
+            kind,
+        }
+    }
+
+    #[test]
+    fn cells_hold_their_pieces_bottom_first() {
+        assert_eq!(BoardCell::from_pieces(Vec::new()), BoardCell::Empty);
+        // A single piece is never a stack
+        assert_eq!(
+            BoardCell::from_pieces(vec![piece(1, 0)]),
+            BoardCell::owned_by(Player(1))
+        );
+        let stack = BoardCell::from_pieces(vec![piece(0, 0), piece(1, 2)]);
+        assert_eq!(stack.get_pieces(), &[piece(0, 0), piece(1, 2)]);
+        assert_eq!(stack.get_top(), Some(piece(1, 2)));
+        // The top piece controls the cell
+        assert!(stack.is_owned_by(Player(1)));
+        assert!(BoardCell::Blocked.get_pieces().is_empty());
+        assert_eq!(BoardCell::Blocked.get_owner(), None);
+        assert!(!BoardCell::Blocked.is_empty());
+    }
+
+    #[test]
+    fn placements_share_points_between_tied_players() {
+        assert_scores(
+            Outcome::Placements(vec![0, 1, 2]),
+            &[2.0 / 3.0, 1.0 / 3.0, 0.0],
+        );
+        // Two winners and a loser
+        assert_scores(Outcome::Placements(vec![0, 1, 0]), &[0.5, 0.0, 0.5]);
+        assert_scores(Outcome::Placements(vec![0, 0, 0, 0]), &[0.25; 4]);
+    }
+
+    #[test]
+    fn points_count_above_the_lowest_total() {
+        assert_scores(
+            Outcome::Points(vec![10.0, 4.0, 7.0]),
+            &[2.0 / 3.0, 0.0, 1.0 / 3.0],
+        );
+        assert_scores(Outcome::Points(vec![3.0, 3.0]), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn utilities_are_clamped_and_scaled() {
+        assert_scores(Outcome::Utilities(vec![3.0, 1.0, -2.0]), &[0.75, 0.25, 0.0]);
+        assert_scores(Outcome::Utilities(vec![0.0, 0.0]), &[0.5, 0.5]);
+    }
+}
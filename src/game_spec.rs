@@ -1,11 +1,84 @@
-use crate::game::{Game, GameState};
-use crate::game_elements::{BoardCell, Player};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::config::{ConfigSchema, GameConfig};
+use crate::eval::{EvaluationFunction, RolloutEvaluationFunction};
+use crate::game::{Game, MoveDelta};
+use crate::game_elements::{Move, Outcome, Player};
+use crate::metadata::GameMetadata;
+
+// Rollouts of the default evaluation function, played uniformly at random
+const DEFAULT_NUM_ROLLOUTS: usize = 8;
+const DEFAULT_MAX_ROLLOUT_LENGTH: usize = 200;
+
+// When the player to move may pass instead of playing one of the spec's moves. Written in
+// snake_case in rule documents.
+#[derive(PartialEq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassPolicy {
+    #[default]
+    Never,
+    // Only when there is no other valid move, as in Othello
+    WhenForced,
+    Always,
+}
 
 pub trait GameSpec {
-    fn get_initial_board(&self) -> Vec<Vec<BoardCell>>;
+    // The config has every parameter of the schema filled in, and num_players is within the
+    // range of the metadata
+    fn get_initial_board(&self, config: &GameConfig, num_players: usize) -> Board;
     fn get_initial_to_move(&self) -> Player;
-    fn get_valid_moves(&self, game: &Game) -> Vec<(usize, usize)>;
-    fn transition(&self, game: &Game, move_row: usize, move_col: usize) -> Box<GameState>;
+    // Every move the player to move can make, not counting passes
+    fn get_valid_moves(&self, game: &Game) -> Vec<Move>;
+    // Reports what the move changes rather than building the next state, so that Game can
+    // apply and undo it in place. The move is one of get_valid_moves.
+    fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta;
     fn is_win(&self, game: &Game, player: Player) -> bool;
     fn is_tie(&self, game: &Game) -> bool;
+
+    // For games that end with more than a single winner or a draw between everyone: returns
+    // the outcome once the game is over. It is checked before is_tie and is_win.
+    fn get_outcome(&self, _game: &Game) -> Option<Outcome> {
+        None
+    }
+
+    // Heuristic the search applies at its depth cutoffs; by default positions are scored by
+    // averaging random playouts
+    fn get_evaluation_function(&self, _game: &Game) -> Box<dyn EvaluationFunction> {
+        Box::new(RolloutEvaluationFunction::new(
+            DEFAULT_NUM_ROLLOUTS,
+            DEFAULT_MAX_ROLLOUT_LENGTH,
+            1.0,
+            None,
+        ))
+    }
+
+    // Whether the move number is part of the position's hash. Searches reuse results by hash,
+    // so specs whose outcome depends on the move number and not just the board and the player
+    // to move need this.
+    fn hash_includes_move_num(&self) -> bool {
+        false
+    }
+
+    // Passes are added to the valid moves by Game and never reach transition
+    fn get_pass_policy(&self) -> PassPolicy {
+        PassPolicy::Never
+    }
+
+    // The name, rules and display settings of the game, which may depend on the resolved config.
+    // Specs that don't describe themselves get a generic name and the default player styles.
+    fn get_metadata(&self, _config: &GameConfig) -> GameMetadata {
+        GameMetadata::default()
+    }
+
+    // Parameters that can be set through GameConfig, with their defaults and supported ranges.
+    // Specs read the chosen values from game.get_config().
+    fn get_config_schema(&self) -> ConfigSchema {
+        ConfigSchema::default()
+    }
+
+    // Optional hint for the search to try moves with a higher value first
+    fn get_move_ordering_hint(&self, _game: &Game, _game_move: &Move) -> i32 {
+        0
+    }
 }
@@ -1,30 +1,188 @@
+mod alphabeta;
+mod board;
+mod clock;
+mod config;
+// Run natively by the tests and the compile pipeline rather than from JS
+#[cfg(test)]
+mod conformance;
+mod error;
 mod eval;
 mod game;
 mod game_elements;
 mod game_spec;
+mod mcts;
+mod metadata;
 mod opt;
+mod ordering;
+mod othello;
+mod push_up_four;
+mod registry;
+mod rule_spec;
+mod rules;
+mod search;
+mod transposition;
 mod user_game;
+mod zobrist;
 
-use crate::eval::RandomEvaluationFunction;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::config::GameConfig;
+use crate::error::GameError;
 use crate::game::Game;
-use crate::game_elements::{GameStatus, Player};
-use crate::opt::minimax_move;
-use crate::user_game::UserGameSpec;
+use crate::game_elements::{BoardCell, GameStatus, Move};
+use crate::metadata::{GameInfo, GameMetadata};
+use crate::opt::analyze_moves;
+use crate::ordering::MoveOrdering;
+use crate::registry::{find_game, get_games, USER_GAME};
+use crate::rule_spec::RuleSpec;
+use crate::rules::RuleDocument;
+use crate::search::{create_searcher, SearchAlgorithm, SearchLimits, SearchResult, Searcher};
+use crate::transposition::TranspositionTable;
 use js_sys::Array;
 use wasm_bindgen::prelude::*;
 
+// Memory budget of the transposition table shared by all searches of a controller
+const TT_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+// Default thinking time per AI move, and the depth at which iterative deepening always stops
+const DEFAULT_TIME_BUDGET_MS: f64 = 500.0;
+const MAX_SEARCH_DEPTH: usize = 64;
+
+// How pieces that belong to no player and blocked cells are shown on the board
+const NEUTRAL_GLYPH: &str = "N";
+const BLOCKED_GLYPH: &str = "#";
+
+// Text for a cell: the symbol of each piece from the bottom of the stack up, followed by its kind
+// when it isn't the default one
+fn render_cell(cell: &BoardCell, metadata: &GameMetadata) -> String {
+    if *cell == BoardCell::Blocked {
+        return BLOCKED_GLYPH.to_string();
+    }
+    cell.get_pieces()
+        .iter()
+        .map(|piece| {
+            let symbol = piece.owner.map_or(NEUTRAL_GLYPH.to_string(), |player| {
+                metadata.get_player_style(player).symbol
+            });
+            if piece.kind == 0 {
+                symbol
+            } else {
+                format!("{}{}", symbol, piece.kind)
+            }
+        })
+        .collect()
+}
+
 #[wasm_bindgen]
 pub struct GameController {
     game: Game,
-    eval_function: RandomEvaluationFunction,
+    tt: Rc<RefCell<TranspositionTable>>,
+    // The searcher playing for each player when it is an AI
+    searchers: Vec<Box<dyn Searcher>>,
+    search_limits: SearchLimits,
+    // When set, searches to exactly this depth instead of using the budgets
+    fixed_search_depth: Option<usize>,
+    last_search_result: Option<SearchResult>,
+}
+
+// Creates a controller for the generated game. Fails if the game doesn't support that many
+// players.
+#[wasm_bindgen]
+pub fn create_game_controller(num_players: usize) -> Result<GameController, JsValue> {
+    let game = find_game(USER_GAME)?.create_game(num_players, &GameConfig::default())?;
+    Ok(GameController::new(game))
+}
+
+// Takes a GameConfig object, e.g. { num_rows: 8, num_cols: 8, win_length: 5 }. Parameters
+// that are left out get the spec's defaults.
+#[wasm_bindgen]
+pub fn create_configured_game_controller(
+    num_players: usize,
+    config: JsValue,
+) -> Result<GameController, JsValue> {
+    create_game_controller_for(USER_GAME, num_players, config)
+}
+
+// Creates a controller for any game from list_games. The config is optional, as in
+// create_configured_game_controller.
+#[wasm_bindgen]
+pub fn create_game_controller_for(
+    name: &str,
+    num_players: usize,
+    config: JsValue,
+) -> Result<GameController, JsValue> {
+    let config: GameConfig = if config.is_undefined() || config.is_null() {
+        GameConfig::default()
+    } else {
+        serde_wasm_bindgen::from_value(config)?
+    };
+    let game = find_game(name)?.create_game(num_players, &config)?;
+    Ok(GameController::new(game))
+}
+
+// The parameters create_configured_game_controller accepts, with defaults and ranges
+#[wasm_bindgen]
+pub fn get_config_schema() -> Result<JsValue, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(
+        &find_game(USER_GAME)?.get_config_schema(),
+    )?)
+}
+
+// Creates a controller for a game described by a JSON rule document instead of a built-in spec.
+// Fails with the reason if the document isn't valid.
+#[wasm_bindgen]
+pub fn create_game_controller_from_rules(
+    rules: &str,
+    num_players: usize,
+) -> Result<GameController, JsValue> {
+    let spec = RuleSpec::from_json(rules)?;
+    let game = Game::new(Box::new(spec), num_players, &GameConfig::default())?;
+    Ok(GameController::new(game))
+}
+
+// Checks a rule document without starting a game, e.g. while it is being written
+#[wasm_bindgen]
+pub fn validate_rules(rules: &str) -> Result<(), JsValue> {
+    RuleDocument::from_json(rules).map_err(GameError::InvalidRules)?;
+    Ok(())
 }
 
+// Every game that can be created, with its name, metadata and config
 #[wasm_bindgen]
-pub fn create_game_controller(num_players: usize) -> GameController {
-    let game: Game = Game::new(Box::new(UserGameSpec), num_players);
-    GameController {
-        game,
-        eval_function: RandomEvaluationFunction::new(num_players),
+pub fn list_games() -> Result<JsValue, JsValue> {
+    let games = get_games()
+        .iter()
+        .map(|entry| entry.get_listing())
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(serde_wasm_bindgen::to_value(&games)?)
+}
+
+impl GameController {
+    fn new(mut game: Game) -> GameController {
+        let num_players = game.get_num_players();
+        // Chosen by the spec to match its game
+        game.set_evaluation_function(Some(game.create_evaluation_function()));
+        let tt = Rc::new(RefCell::new(TranspositionTable::new(
+            TT_MEMORY_BYTES,
+            num_players,
+        )));
+        let searchers = (0..num_players)
+            .map(|_| create_searcher(SearchAlgorithm::Minimax, &tt))
+            .collect();
+        GameController {
+            game,
+            tt,
+            searchers,
+            search_limits: SearchLimits {
+                time_budget_ms: Some(DEFAULT_TIME_BUDGET_MS),
+                node_budget: None,
+                max_depth: MAX_SEARCH_DEPTH,
+                move_ordering: true,
+            },
+            fixed_search_depth: None,
+            last_search_result: None,
+        }
     }
 }
 
@@ -32,62 +190,249 @@ pub fn create_game_controller(num_players: usize) -> GameController {
 impl GameController {
     pub fn get_board(&self) -> Array {
         let rust_board = self.game.get_board();
-        let js_board = Array::new_with_length(rust_board.len() as u32);
-        for (i, row) in rust_board.iter().enumerate() {
+        let metadata = self.game.get_metadata();
+        let js_board = Array::new_with_length(rust_board.get_num_rows() as u32);
+        for (i, row) in rust_board.rows().enumerate() {
             let js_row = Array::new_with_length(row.len() as u32);
-            for (j, &cell) in row.iter().enumerate() {
-                let value = match cell {
-                    None => "",
-                    Some(Player::Player0) => "X",
-                    Some(Player::Player1) => "O",
-                    Some(Player::Player2) => "Z",
-                    Some(Player::Player3) => "W",
-                };
-                js_row.set(j as u32, JsValue::from_str(value));
+            for (j, cell) in row.iter().enumerate() {
+                js_row.set(j as u32, JsValue::from_str(&render_cell(cell, &metadata)));
             }
             js_board.set(i as u32, js_row.into());
         }
         js_board
     }
 
-    pub fn get_to_move(&self) -> Player {
-        self.game.get_to_move()
+    // The board as rows of cells serialized as described on BoardCell, for displays that need
+    // piece kinds and stacks rather than get_board's text
+    pub fn get_board_cells(&self) -> Result<JsValue, JsValue> {
+        let rows: Vec<&[BoardCell]> = self.game.get_board().rows().collect();
+        Ok(serde_wasm_bindgen::to_value(&rows)?)
+    }
+
+    // The name and rules of the game, with each player's symbol and color and the labels of the
+    // rows and columns of this board
+    pub fn get_game_info(&self) -> Result<JsValue, JsValue> {
+        let info = GameInfo::new(
+            self.game.get_metadata(),
+            self.game.get_num_players(),
+            self.game.get_num_rows(),
+            self.game.get_num_cols(),
+        );
+        Ok(serde_wasm_bindgen::to_value(&info)?)
+    }
+
+    // Players are numbered from 0
+    pub fn get_to_move(&self) -> usize {
+        self.game.get_to_move().to_usize()
     }
 
     pub fn get_move_num(&self) -> usize {
         self.game.get_move_num()
     }
 
-    pub fn get_game_status(&self) -> GameStatus {
-        self.game.get_game_status()
+    // "Ongoing", "Tie", { Win: player } or { Over: outcome }, where the outcome is one of
+    // { Placements: [...] }, { Points: [...] } or { Utilities: [...] } with an entry per player
+    pub fn get_game_status(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.game.get_game_status())?)
+    }
+
+    // The config the game was created with, including the defaults that were filled in
+    pub fn get_config(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(self.game.get_config())?)
+    }
+
+    // One entry per player: the outcome once the game is over, an estimate before that
+    pub fn get_score(&self) -> Vec<f64> {
+        self.game.get_score()
+    }
+
+    pub fn set_search_algorithm(&mut self, algorithm: SearchAlgorithm) {
+        for searcher in self.searchers.iter_mut() {
+            *searcher = create_searcher(algorithm, &self.tt);
+        }
+    }
+
+    pub fn set_player_search_algorithm(
+        &mut self,
+        player: usize,
+        algorithm: SearchAlgorithm,
+    ) -> Result<(), JsValue> {
+        let player = self.game.get_player(player)?;
+        self.searchers[player.to_usize()] = create_searcher(algorithm, &self.tt);
+        Ok(())
+    }
+
+    pub fn set_time_budget_ms(&mut self, time_budget_ms: Option<f64>) {
+        self.search_limits.time_budget_ms = time_budget_ms;
     }
 
-    pub fn make_ai_move(&mut self) {
-        const SEARCH_DEPTH: usize = 3;
-        let (_, move_option) = minimax_move(&mut self.game, &self.eval_function, SEARCH_DEPTH);
+    pub fn set_node_budget(&mut self, node_budget: Option<usize>) {
+        self.search_limits.node_budget = node_budget;
+    }
 
-        if let Some((move_row, move_col)) = move_option {
-            // Execute the move
-            self.game.transition(move_row, move_col);
-        } else {
-            // Handle the case where there's no move
-            println!("No valid AI move found");
+    // Turning move ordering off is mostly useful to see how many nodes it saves
+    pub fn set_move_ordering(&mut self, move_ordering: bool) {
+        self.search_limits.move_ordering = move_ordering;
+    }
+
+    // A search has to look at least one move ahead to pick a move
+    pub fn set_fixed_search_depth(&mut self, search_depth: Option<usize>) -> Result<(), JsValue> {
+        if search_depth == Some(0) {
+            return Err(GameError::InvalidConfig(
+                "The search depth must be at least 1".to_string(),
+            )
+            .into());
         }
+        self.fixed_search_depth = search_depth;
+        Ok(())
+    }
+
+    // The depth of the deepest search iteration completed by the last AI move,
+    // or the depth of the deepest tree node for MCTS
+    pub fn get_last_search_depth(&self) -> usize {
+        self.last_search_result
+            .as_ref()
+            .map_or(0, |result| result.depth)
+    }
+
+    // Statistics, score vector and principal variation of the last AI move's search
+    pub fn get_last_search_result(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.last_search_result)?)
+    }
+
+    // Score vectors of every valid move for the player to move, searched to the given depth
+    pub fn analyze_moves(&mut self, depth: usize) -> Result<JsValue, JsValue> {
+        if !(1..=MAX_SEARCH_DEPTH).contains(&depth) {
+            return Err(GameError::InvalidConfig(format!(
+                "The analysis depth must be between 1 and {}",
+                MAX_SEARCH_DEPTH
+            ))
+            .into());
+        }
+        let analyses = analyze_moves(
+            &mut self.game,
+            depth,
+            &mut self.tt.borrow_mut(),
+            Some(&mut MoveOrdering::default()),
+        );
+        Ok(serde_wasm_bindgen::to_value(&analyses)?)
+    }
+
+    pub fn make_ai_move(&mut self) -> Result<(), JsValue> {
+        if self.game.get_game_status() != GameStatus::Ongoing {
+            return Err(GameError::GameOver.into());
+        }
+        let overridden_limits;
+        let limits = match self.fixed_search_depth {
+            Some(search_depth) => {
+                overridden_limits = SearchLimits {
+                    time_budget_ms: None,
+                    node_budget: None,
+                    max_depth: search_depth,
+                    ..self.search_limits
+                };
+                &overridden_limits
+            }
+            // Without either budget iterative deepening would only stop at MAX_SEARCH_DEPTH
+            None if !self.search_limits.has_budget() => {
+                overridden_limits = SearchLimits {
+                    time_budget_ms: Some(DEFAULT_TIME_BUDGET_MS),
+                    ..self.search_limits
+                };
+                &overridden_limits
+            }
+            None => &self.search_limits,
+        };
+        let searcher = &mut self.searchers[self.game.get_to_move().to_usize()];
+        let result = searcher.search(&mut self.game, limits);
+        let move_option = result.best_move.clone();
+        self.last_search_result = Some(result);
+
+        let game_move = move_option.ok_or(GameError::NoValidMoves)?;
+        Ok(self.game.make_move(&game_move)?)
+    }
+
+    // Plays one of the moves from get_valid_moves, passed back as it was serialized
+    pub fn make_move(&mut self, game_move: JsValue) -> Result<(), JsValue> {
+        let game_move: Move = serde_wasm_bindgen::from_value(game_move)?;
+        Ok(self.game.make_move(&game_move)?)
+    }
+
+    // Shorthand for placing a piece on an empty cell
+    pub fn make_human_move(&mut self, move_row: usize, move_col: usize) -> Result<(), JsValue> {
+        Ok(self.game.make_move(&Move::place(move_row, move_col))?)
+    }
+
+    // Takes back the last move, whoever made it
+    pub fn undo(&mut self) -> Result<(), JsValue> {
+        Ok(self.game.undo_move()?)
+    }
+
+    pub fn redo(&mut self) -> Result<(), JsValue> {
+        Ok(self.game.redo_move()?)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.game.can_undo()
     }
 
-    pub fn make_human_move(&mut self, move_row: usize, move_col: usize) {
-        self.game.transition(move_row, move_col);
+    pub fn can_redo(&self) -> bool {
+        self.game.can_redo()
     }
 
-    pub fn get_valid_moves(&self) -> Array {
-        let valid_moves = Array::new();
-        for &(row, col) in self.game.get_valid_moves().iter() {
-            let arr = Array::new();
-            arr.push(&JsValue::from_f64(row as f64));
-            arr.push(&JsValue::from_f64(col as f64));
-            valid_moves.push(&arr.into());
+    // Takes back moves up to and including the last one the player made, so that the moves of
+    // the AIs in between are rewound too and it is the player's turn again. Without a previous
+    // turn of the player, goes back to the start of the game.
+    pub fn undo_to_last_turn(&mut self, player: usize) -> Result<(), JsValue> {
+        let player = self.game.get_player(player)?;
+        self.game.undo_move()?;
+        while self.game.get_to_move() != player && self.game.can_undo() {
+            self.game.undo_move()?;
         }
+        Ok(())
+    }
+
+    // Whether the player to move may pass instead of placing
+    pub fn can_pass(&self) -> bool {
+        self.game.get_valid_moves().contains(&Move::Pass)
+    }
+
+    pub fn make_pass_move(&mut self) -> Result<(), JsValue> {
+        Ok(self.game.make_move(&Move::Pass)?)
+    }
 
-        valid_moves
+    // Every valid move, serialized as described on Move
+    pub fn get_valid_moves(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.game.get_valid_moves())?)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_elements::{Piece, Player};
+
+    #[test]
+    fn cells_render_each_piece_from_the_bottom_up() {
+        let metadata = GameMetadata::default();
+        let render = |cell: BoardCell| render_cell(&cell, &metadata);
+        assert_eq!(render(BoardCell::Empty), "");
+        assert_eq!(render(BoardCell::Blocked), BLOCKED_GLYPH);
+        assert_eq!(render(BoardCell::owned_by(Player(1))), "O");
+        let stack = BoardCell::from_pieces(vec![
+            Piece {
+                owner: Some(Player(0)),
+                kind: 0,
+            },
+            Piece {
+                owner: None,
+                kind: 0,
+            },
+            Piece {
+                owner: Some(Player(1)),
+                kind: 2,
+            },
+        ]);
+        assert_eq!(render(stack), "XNO2");
+    }
+}
@@ -0,0 +1,302 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::clock::now_ms;
+use crate::game::Game;
+use crate::game_elements::{GameStatus, Move};
+use crate::search::{SearchLimits, SearchResult, SearchStats, Searcher};
+
+// UCT exploration constant; rewards are score vector entries in [0, 1]
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+// Rollouts that don't reach a terminal state within this many moves are scored as they stand by
+// the game's evaluation function. This is separate from limits.max_depth, which only bounds the
+// tree.
+const MAX_ROLLOUT_LENGTH: usize = 500;
+
+// Used when neither a time nor a node budget is given
+const DEFAULT_ITERATIONS: usize = 10_000;
+
+struct Node {
+    move_made: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried_moves: Vec<Move>,
+    visits: usize,
+    // Sum of the score vectors of all playouts through this node, one entry per player
+    reward_sums: Vec<f64>,
+}
+
+impl Node {
+    fn new(game: &Game, move_made: Option<Move>, parent: Option<usize>) -> Self {
+        let untried_moves = if game.get_game_status() == GameStatus::Ongoing {
+            game.get_valid_moves()
+        } else {
+            Vec::new()
+        };
+        Node {
+            move_made,
+            parent,
+            children: Vec::new(),
+            untried_moves,
+            visits: 0,
+            reward_sums: vec![0.0; game.get_num_players()],
+        }
+    }
+}
+
+// Playouts replace the evaluation function, so any game can be searched without a heuristic
+pub struct MctsSearcher;
+
+impl Searcher for MctsSearcher {
+    fn search(&mut self, game: &mut Game, limits: &SearchLimits) -> SearchResult {
+        mcts_move(game, limits)
+    }
+}
+
+// Runs UCT until the budget is used up, growing the tree at most limits.max_depth plies deep. The
+// principal variation follows the most visited children, and each playout counts as one leaf
+// evaluation.
+pub fn mcts_move(game: &mut Game, limits: &SearchLimits) -> SearchResult {
+    let start_ms = now_ms();
+    let mut stats = SearchStats::default();
+    if game.get_game_status() != GameStatus::Ongoing {
+        return SearchResult::new(Vec::new(), game.get_score(), 0, 0, &stats, start_ms);
+    }
+    let tree = grow_tree(game, limits, &mut stats);
+
+    let mut principal_variation = Vec::new();
+    let mut score = game.get_score();
+    let mut node_idx = 0;
+    while let Some(&child_idx) = tree[node_idx]
+        .children
+        .iter()
+        .max_by_key(|&&child_idx| tree[child_idx].visits)
+    {
+        let child = &tree[child_idx];
+        if principal_variation.is_empty() {
+            score = child
+                .reward_sums
+                .iter()
+                .map(|sum| sum / child.visits as f64)
+                .collect();
+        }
+        principal_variation.push(child.move_made.clone().unwrap());
+        node_idx = child_idx;
+    }
+    SearchResult::new(
+        principal_variation,
+        score,
+        stats.max_depth_reached,
+        tree.len(),
+        &stats,
+        start_ms,
+    )
+}
+
+// Runs the UCT iterations and returns the tree, with the root at index 0. The game is back at the
+// root position afterwards.
+fn grow_tree(game: &mut Game, limits: &SearchLimits, stats: &mut SearchStats) -> Vec<Node> {
+    let mut rng = rand::thread_rng();
+    let deadline_ms = limits.time_budget_ms.map(|budget| now_ms() + budget);
+    let max_iterations = match (limits.node_budget, limits.time_budget_ms) {
+        (None, None) => Some(DEFAULT_ITERATIONS),
+        (node_budget, _) => node_budget,
+    };
+
+    // Nodes live in an arena and refer to each other by index
+    let mut tree = vec![Node::new(game, None, None)];
+    let mut iterations = 0;
+    loop {
+        // At least one iteration runs, so there is always a move to return
+        if iterations > 0
+            && (max_iterations.is_some_and(|max| iterations >= max)
+                || deadline_ms.is_some_and(|deadline| now_ms() >= deadline))
+        {
+            break;
+        }
+        iterations += 1;
+
+        // Selection: descend through fully expanded nodes, each player picking by their own reward
+        let mut node_idx = 0;
+        let mut tree_depth = 0;
+        while tree[node_idx].untried_moves.is_empty()
+            && !tree[node_idx].children.is_empty()
+            && tree_depth < limits.max_depth
+        {
+            let player_idx = game.get_to_move().to_usize();
+            node_idx = select_child(&tree, node_idx, player_idx);
+            game.transition(tree[node_idx].move_made.as_ref().unwrap());
+            tree_depth += 1;
+        }
+
+        // Expansion: add one untried child, unless the tree is already as deep as allowed
+        if !tree[node_idx].untried_moves.is_empty() && tree_depth < limits.max_depth {
+            let move_idx = rng.gen_range(0..tree[node_idx].untried_moves.len());
+            let game_move = tree[node_idx].untried_moves.swap_remove(move_idx);
+            game.transition(&game_move);
+            tree_depth += 1;
+
+            let child = Node::new(game, Some(game_move), Some(node_idx));
+            tree.push(child);
+            let child_idx = tree.len() - 1;
+            tree[node_idx].children.push(child_idx);
+            node_idx = child_idx;
+        }
+        stats.max_depth_reached = stats.max_depth_reached.max(tree_depth);
+
+        // Simulation: play random moves, then restore the position at the new node
+        let mut rollout_length = 0;
+        while rollout_length < MAX_ROLLOUT_LENGTH && game.get_game_status() == GameStatus::Ongoing {
+            match game.get_valid_moves().choose(&mut rng) {
+                Some(game_move) => game.transition(game_move),
+                None => break,
+            }
+            rollout_length += 1;
+        }
+        let reward = game.get_score();
+        for _ in 0..rollout_length {
+            game.undo_transition();
+        }
+
+        // Backpropagation: every node on the path collects the full score vector
+        let mut current = Some(node_idx);
+        while let Some(idx) = current {
+            let node = &mut tree[idx];
+            node.visits += 1;
+            for (sum, value) in node.reward_sums.iter_mut().zip(reward.iter()) {
+                *sum += value;
+            }
+            current = node.parent;
+        }
+        for _ in 0..tree_depth {
+            game.undo_transition();
+        }
+    }
+
+    stats.leaf_evaluations = iterations;
+    tree
+}
+
+fn select_child(tree: &[Node], node_idx: usize, player_idx: usize) -> usize {
+    let log_visits = (tree[node_idx].visits as f64).ln();
+    let uct = |child_idx: usize| {
+        let child = &tree[child_idx];
+        let visits = child.visits as f64;
+        child.reward_sums[player_idx] / visits + EXPLORATION * (log_visits / visits).sqrt()
+    };
+    *tree[node_idx]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| uct(a).partial_cmp(&uct(b)).unwrap())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::game_elements::Player;
+    use crate::push_up_four::PushUpFourSpec;
+    use crate::rule_spec::RuleSpec;
+
+    fn in_a_row_game(rows: usize, cols: usize, length: usize, num_players: usize) -> Game {
+        let json = format!(
+            r#"{{
+                "name": "In a row",
+                "max_players": 3,
+                "board": {{ "rows": {}, "cols": {} }},
+                "moves": [{{ "type": "place" }}],
+                "win": [{{ "type": "in_a_row", "length": {} }}]
+            }}"#,
+            rows, cols, length
+        );
+        let spec = RuleSpec::from_json(&json).unwrap();
+        Game::new(Box::new(spec), num_players, &GameConfig::default()).unwrap()
+    }
+
+    fn node_limits(node_budget: usize) -> SearchLimits {
+        SearchLimits {
+            time_budget_ms: None,
+            node_budget: Some(node_budget),
+            max_depth: 64,
+            move_ordering: true,
+        }
+    }
+
+    #[test]
+    fn tree_stays_within_max_depth() {
+        let mut game = Game::new(Box::new(PushUpFourSpec), 2, &GameConfig::default()).unwrap();
+        for max_depth in 1..=3 {
+            let limits = SearchLimits {
+                time_budget_ms: None,
+                node_budget: Some(300),
+                max_depth,
+                move_ordering: true,
+            };
+            let result = mcts_move(&mut game, &limits);
+            assert!(result.best_move.is_some());
+            assert!(result.max_depth_reached <= max_depth);
+            assert!(result.principal_variation.len() <= max_depth);
+        }
+    }
+
+    #[test]
+    fn takes_an_immediate_win() {
+        let mut game = in_a_row_game(3, 3, 3, 2);
+        for game_move in [
+            Move::place(0, 0),
+            Move::place(1, 0),
+            Move::place(0, 1),
+            Move::place(1, 1),
+        ] {
+            game.make_move(&game_move).unwrap();
+        }
+        let result = mcts_move(&mut game, &node_limits(2000));
+        assert_eq!(result.best_move, Some(Move::place(0, 2)));
+        assert_eq!(result.score, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn reward_sums_track_each_players_results() {
+        let mut game = in_a_row_game(2, 3, 2, 3);
+        let mut stats = SearchStats::default();
+        let tree = grow_tree(&mut game, &node_limits(500), &mut stats);
+        for (idx, node) in tree.iter().enumerate() {
+            // Every score vector sums to 1
+            let total: f64 = node.reward_sums.iter().sum();
+            assert!((total - node.visits as f64).abs() < 1e-9);
+
+            // Replay the path from the root to see how the game stands at this node
+            let mut path = Vec::new();
+            let mut current = Some(idx);
+            while let Some(node_idx) = current {
+                path.extend(tree[node_idx].move_made.clone());
+                current = tree[node_idx].parent;
+            }
+            for game_move in path.iter().rev() {
+                game.transition(game_move);
+            }
+            if let GameStatus::Win(Player(winner)) = game.get_game_status() {
+                for (player, &sum) in node.reward_sums.iter().enumerate() {
+                    let expected = if player == winner {
+                        node.visits as f64
+                    } else {
+                        0.0
+                    };
+                    assert_eq!(sum, expected);
+                }
+            }
+            for _ in 0..path.len() {
+                game.undo_transition();
+            }
+        }
+    }
+
+    #[test]
+    fn an_empty_node_budget_still_finds_a_move() {
+        let mut game = Game::new(Box::new(PushUpFourSpec), 2, &GameConfig::default()).unwrap();
+        let result = mcts_move(&mut game, &node_limits(0));
+        assert!(result.best_move.is_some());
+    }
+}
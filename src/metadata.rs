@@ -0,0 +1,164 @@
+// How a game presents itself: its name and rules, and how players and coordinates are shown
+
+use serde::{Deserialize, Serialize};
+
+use crate::game_elements::{Player, MAX_PLAYERS};
+
+// Used for players a spec doesn't style itself
+const DEFAULT_SYMBOLS: [&str; MAX_PLAYERS] = ["X", "O", "Z", "W", "A", "B", "C", "D"];
+const DEFAULT_COLORS: [&str; MAX_PLAYERS] = [
+    "#e53935", "#1e88e5", "#43a047", "#fdd835", "#8e24aa", "#fb8c00", "#00acc1", "#6d4c41",
+];
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PlayerStyle {
+    // Shown on the board for the player's pieces
+    pub symbol: String,
+    // Any CSS color
+    pub color: String,
+}
+
+impl PlayerStyle {
+    pub fn new(symbol: &str, color: &str) -> Self {
+        PlayerStyle {
+            symbol: symbol.to_string(),
+            color: color.to_string(),
+        }
+    }
+}
+
+// How rows and columns are named
+#[allow(dead_code)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum CoordinateStyle {
+    #[default]
+    // Rows and columns numbered from 1 at the top left
+    Numeric,
+    // Columns lettered from a, rows numbered from 1 at the top, as in Othello
+    Algebraic,
+    // Columns lettered from a, rows numbered from 1 at the bottom, as in chess
+    AlgebraicFromBottom,
+}
+
+// How pieces get to their cells
+#[allow(dead_code)]
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum MoveStyle {
+    // Pieces go on any cell the rules allow
+    Placement,
+    // Pieces are dropped into a column and come to rest at its end
+    Gravity,
+    // Pieces on the board move from cell to cell
+    Movement,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GameMetadata {
+    pub name: String,
+    // A short summary of the rules for the players
+    pub rules: String,
+    pub min_players: usize,
+    pub max_players: usize,
+    // Indexed by player; players past the end of the list get a default style
+    pub player_styles: Vec<PlayerStyle>,
+    pub coordinate_style: CoordinateStyle,
+    pub move_style: MoveStyle,
+}
+
+impl Default for GameMetadata {
+    fn default() -> Self {
+        GameMetadata {
+            name: "Untitled game".to_string(),
+            rules: String::new(),
+            min_players: 1,
+            max_players: MAX_PLAYERS,
+            player_styles: Vec::new(),
+            coordinate_style: CoordinateStyle::default(),
+            move_style: MoveStyle::Placement,
+        }
+    }
+}
+
+impl GameMetadata {
+    pub fn get_player_style(&self, player: Player) -> PlayerStyle {
+        let player_idx = player.to_usize();
+        self.player_styles
+            .get(player_idx)
+            .cloned()
+            .unwrap_or_else(|| {
+                PlayerStyle::new(DEFAULT_SYMBOLS[player_idx], DEFAULT_COLORS[player_idx])
+            })
+    }
+}
+
+// Spreadsheet-style column letters: a to z, then aa, ab and so on
+fn column_letters(col: usize) -> String {
+    let mut letters = Vec::new();
+    let mut remaining = col + 1;
+    while remaining > 0 {
+        remaining -= 1;
+        letters.push((b'a' + (remaining % 26) as u8) as char);
+        remaining /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+impl CoordinateStyle {
+    // Labels of the rows from the top of the board down
+    pub fn get_row_labels(self, num_rows: usize) -> Vec<String> {
+        (0..num_rows)
+            .map(|row| match self {
+                CoordinateStyle::Numeric | CoordinateStyle::Algebraic => (row + 1).to_string(),
+                CoordinateStyle::AlgebraicFromBottom => (num_rows - row).to_string(),
+            })
+            .collect()
+    }
+
+    // Labels of the columns from left to right
+    pub fn get_col_labels(self, num_cols: usize) -> Vec<String> {
+        (0..num_cols)
+            .map(|col| match self {
+                CoordinateStyle::Numeric => (col + 1).to_string(),
+                CoordinateStyle::Algebraic | CoordinateStyle::AlgebraicFromBottom => {
+                    column_letters(col)
+                }
+            })
+            .collect()
+    }
+}
+
+// The metadata resolved for one game in progress, as returned by get_game_info
+#[derive(Serialize)]
+pub struct GameInfo {
+    pub name: String,
+    pub rules: String,
+    pub min_players: usize,
+    pub max_players: usize,
+    // One per player in the game
+    pub players: Vec<PlayerStyle>,
+    pub row_labels: Vec<String>,
+    pub col_labels: Vec<String>,
+    pub move_style: MoveStyle,
+}
+
+impl GameInfo {
+    pub fn new(
+        metadata: GameMetadata,
+        num_players: usize,
+        num_rows: usize,
+        num_cols: usize,
+    ) -> Self {
+        GameInfo {
+            players: (0..num_players)
+                .map(|player_idx| metadata.get_player_style(Player(player_idx)))
+                .collect(),
+            row_labels: metadata.coordinate_style.get_row_labels(num_rows),
+            col_labels: metadata.coordinate_style.get_col_labels(num_cols),
+            name: metadata.name,
+            rules: metadata.rules,
+            min_players: metadata.min_players,
+            max_players: metadata.max_players,
+            move_style: metadata.move_style,
+        }
+    }
+}
@@ -1,87 +1,382 @@
-use std::hash::{Hash, Hasher};
-use std::collections::hash_map::DefaultHasher;
+use std::cell::RefCell;
 use std::collections::HashSet;
+use std::rc::Rc;
 
-use crate::eval::EvaluationFunction;
-use crate::game::{Game, GameState};
-use crate::game_elements::GameStatus;
+use serde::Serialize;
+
+use crate::clock::now_ms;
+use crate::game::Game;
+use crate::game_elements::{GameStatus, Move};
+use crate::ordering::MoveOrdering;
+use crate::search::{SearchBudget, SearchLimits, SearchResult, SearchStats, Searcher};
+use crate::transposition::TranspositionTable;
+
+// Marks a subtree in which no pruning depended on an alpha from outside of it
+const NO_CUTOFF: usize = usize::MAX;
+
+// Stored as the depth of subtrees searched all the way to terminal states
+const SOLVED_DEPTH: usize = usize::MAX;
+
+struct SearchContext<'a> {
+    tt: &'a mut TranspositionTable,
+    // None searches the moves in the order the spec gives them
+    ordering: Option<&'a mut MoveOrdering>,
+    search_depth: usize,
+    alphas: Vec<f64>,
+    // Depth of the node that set each player's alpha
+    alpha_depths: Vec<usize>,
+    budget: SearchBudget,
+    stats: SearchStats,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new(
+        game: &Game,
+        tt: &'a mut TranspositionTable,
+        mut ordering: Option<&'a mut MoveOrdering>,
+    ) -> Self {
+        let num_players = game.get_num_players();
+        tt.new_search();
+        if let Some(ordering) = ordering.as_deref_mut() {
+            ordering.new_search(game);
+        }
+        SearchContext {
+            tt,
+            ordering,
+            search_depth: 0,
+            alphas: vec![f64::NEG_INFINITY; num_players],
+            alpha_depths: vec![NO_CUTOFF; num_players],
+            budget: SearchBudget::unlimited(),
+            stats: SearchStats::default(),
+        }
+    }
+
+    fn leaf(&mut self, d: usize, score: Vec<f64>, solved: bool) -> NodeResult {
+        self.stats.leaf_evaluations += 1;
+        self.stats.max_depth_reached = self.stats.max_depth_reached.max(d);
+        NodeResult {
+            score,
+            principal_variation: Vec::new(),
+            cutoff_depth: NO_CUTOFF,
+            solved,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct MoveAnalysis {
+    #[serde(rename = "move")]
+    pub move_made: Move,
+    pub score: Vec<f64>,
+    // Starts with move_made
+    pub principal_variation: Vec<Move>,
+}
+
+struct NodeResult {
+    score: Vec<f64>,
+    // Starts with the best move; empty at leaves
+    principal_variation: Vec<Move>,
+    // Shallowest depth whose alpha caused a prune in the subtree
+    cutoff_depth: usize,
+    // Whether every leaf of the subtree was a terminal state rather than a depth cutoff
+    solved: bool,
+}
+
+pub struct MaxnSearcher {
+    // Shared with the max^n searchers of the other players, as entries hold every player's score
+    tt: Rc<RefCell<TranspositionTable>>,
+    ordering: MoveOrdering,
+}
+
+impl MaxnSearcher {
+    pub fn new(tt: Rc<RefCell<TranspositionTable>>) -> Self {
+        MaxnSearcher {
+            tt,
+            ordering: MoveOrdering::default(),
+        }
+    }
+}
+
+impl Searcher for MaxnSearcher {
+    fn search(&mut self, game: &mut Game, limits: &SearchLimits) -> SearchResult {
+        let mut tt = self.tt.borrow_mut();
+        let ordering = limits.move_ordering.then_some(&mut self.ordering);
+        if limits.has_budget() {
+            iterative_deepening_move(game, limits, &mut tt, ordering)
+        } else {
+            // Nothing can cut the search short, so shallower iterations would be wasted
+            minimax_move(game, limits.max_depth, &mut tt, ordering)
+        }
+    }
+}
 
 pub fn minimax_move(
     game: &mut Game,
-    eval_func: &dyn EvaluationFunction,
     search_depth: usize,
-) -> (f64, Option<(usize, usize)>) {
-    let num_players = game.get_num_players();
-    let mut alphas = vec![f64::NEG_INFINITY; num_players];
-    let (best_score, best_move) = dfs(game, 0, &mut alphas, eval_func, search_depth);
-
-    (
-        best_score[game.get_to_move().to_usize()],
-        best_move,
-    )
+    tt: &mut TranspositionTable,
+    ordering: Option<&mut MoveOrdering>,
+) -> SearchResult {
+    let start_ms = now_ms();
+    let mut ctx = SearchContext::new(game, tt, ordering);
+    ctx.search_depth = search_depth;
+    let result = dfs(game, 0, &mut ctx);
+    finish_search(game, &ctx, result, search_depth, start_ms)
 }
 
-fn dfs(
+// Searches with increasing depth until a limit is hit, returning the result of the deepest
+// completed iteration
+pub fn iterative_deepening_move(
+    game: &mut Game,
+    limits: &SearchLimits,
+    tt: &mut TranspositionTable,
+    ordering: Option<&mut MoveOrdering>,
+) -> SearchResult {
+    let start_ms = now_ms();
+    let mut ctx = SearchContext::new(game, tt, ordering);
+    ctx.budget = SearchBudget::new(limits);
+
+    let mut best: Option<(NodeResult, usize)> = None;
+    for depth in 1..=limits.max_depth {
+        ctx.search_depth = depth;
+        ctx.budget.abortable = best
+            .as_ref()
+            .is_some_and(|(result, _)| !result.principal_variation.is_empty());
+        let result = dfs(game, 0, &mut ctx);
+        if ctx.budget.is_aborted() {
+            break;
+        }
+
+        // Searching deeper cannot change anything once the whole tree has been seen
+        let solved = result.solved;
+        best = Some((result, depth));
+        if solved {
+            break;
+        }
+    }
+    match best {
+        Some((result, depth)) => finish_search(game, &ctx, result, depth, start_ms),
+        None => finish_search(game, &ctx, empty_result(game), 0, start_ms),
+    }
+}
+
+// Searches every valid move to the same depth. No move is pruned at the root, so each score
+// vector is the value of that move rather than a bound.
+pub fn analyze_moves(
     game: &mut Game,
-    d: usize,
-    alphas: &mut Vec<f64>,
-    eval_func: &dyn EvaluationFunction,
     search_depth: usize,
-) -> (Vec<f64>, Option<(usize, usize)>) {
+    tt: &mut TranspositionTable,
+    ordering: Option<&mut MoveOrdering>,
+) -> Vec<MoveAnalysis> {
+    let mut ctx = SearchContext::new(game, tt, ordering);
+    // The root itself is never a leaf, so at least one ply is searched
+    ctx.search_depth = search_depth.max(1);
+    if game.get_game_status() != GameStatus::Ongoing {
+        return Vec::new();
+    }
+
+    let mut analyses = Vec::new();
+    for game_move in game.get_valid_moves() {
+        game.transition(&game_move);
+        let mut child = dfs(game, 1, &mut ctx);
+        let max_len = if child.solved {
+            usize::MAX
+        } else {
+            ctx.search_depth - 1
+        };
+        extend_principal_variation(game, ctx.tt, &mut child.principal_variation, max_len);
+        game.undo_transition();
+
+        child.principal_variation.insert(0, game_move.clone());
+        analyses.push(MoveAnalysis {
+            move_made: game_move,
+            score: child.score,
+            principal_variation: child.principal_variation,
+        });
+    }
+    analyses
+}
+
+fn empty_result(game: &Game) -> NodeResult {
+    NodeResult {
+        score: vec![0.0; game.get_num_players()],
+        principal_variation: Vec::new(),
+        cutoff_depth: NO_CUTOFF,
+        solved: false,
+    }
+}
+
+fn finish_search(
+    game: &mut Game,
+    ctx: &SearchContext,
+    mut result: NodeResult,
+    depth: usize,
+    start_ms: f64,
+) -> SearchResult {
+    // Nodes answered from the transposition table cut the line short, so extend it from there
+    let max_len = if result.solved { usize::MAX } else { depth };
+    extend_principal_variation(game, ctx.tt, &mut result.principal_variation, max_len);
+    SearchResult::new(
+        result.principal_variation,
+        result.score,
+        depth,
+        ctx.budget.get_nodes(),
+        &ctx.stats,
+        start_ms,
+    )
+}
+
+fn extend_principal_variation(
+    game: &mut Game,
+    tt: &TranspositionTable,
+    principal_variation: &mut Vec<Move>,
+    max_len: usize,
+) {
+    for game_move in principal_variation.iter() {
+        game.transition(game_move);
+    }
+    let mut num_moves = principal_variation.len();
+    while principal_variation.len() < max_len && game.get_game_status() == GameStatus::Ongoing {
+        // Stored moves may come from a hash collision, so check them before playing them
+        let next_move = tt
+            .probe(game.get_hash())
+            .and_then(|entry| entry.best_move.clone());
+        match next_move {
+            Some(next_move) if game.get_valid_moves().contains(&next_move) => {
+                game.transition(&next_move);
+                principal_variation.push(next_move);
+                num_moves += 1;
+            }
+            _ => break,
+        }
+    }
+    for _ in 0..num_moves {
+        game.undo_transition();
+    }
+}
+
+fn dfs(game: &mut Game, d: usize, ctx: &mut SearchContext) -> NodeResult {
+    if ctx.budget.visit_node() {
+        return empty_result(game);
+    }
+
     let status = game.get_game_status();
     if status != GameStatus::Ongoing {
-        return (game.get_score(), None);
+        return ctx.leaf(d, game.get_score(), true);
+    }
+    if d == ctx.search_depth {
+        return ctx.leaf(d, game.get_score(), false);
     }
-    if d == search_depth {
-        return (eval_func.evaluate(game.get_state()), None);
+
+    // Reuse the result if this position was already searched at least as deeply
+    let remaining_depth = ctx.search_depth - d;
+    let mut tt_move = None;
+    if let Some(entry) = ctx.tt.probe(game.get_hash()) {
+        if entry.exact && entry.depth >= remaining_depth {
+            if let Some(best_move) = &entry.best_move {
+                return NodeResult {
+                    score: entry.score.clone(),
+                    principal_variation: vec![best_move.clone()],
+                    cutoff_depth: NO_CUTOFF,
+                    solved: entry.depth == SOLVED_DEPTH,
+                };
+            }
+        }
+        // A shallower or bounded result still tells us which move to try first
+        tt_move = entry.best_move.clone();
     }
 
     let player_idx = game.get_to_move().to_usize();
-    let old_alpha = alphas[player_idx];
-    let moves = game.get_valid_moves();
-    let mut best_move = None;
+    let old_alpha = ctx.alphas[player_idx];
+    let old_alpha_depth = ctx.alpha_depths[player_idx];
+    let mut moves = game.get_valid_moves();
+    if moves.is_empty() {
+        // The player is stuck in a game without passes, so nothing below can change the score
+        return ctx.leaf(d, game.get_score(), true);
+    }
+    if let Some(ordering) = &ctx.ordering {
+        ordering.order_moves(game, &mut moves, d, tt_move.as_ref());
+    }
+    let mut principal_variation = Vec::new();
     let mut best_score: Option<Vec<f64>> = None;
+    let mut cutoff_depth = NO_CUTOFF;
+    let mut solved = true;
 
     // Each level of the game tree starts with a fresh HashSet of the hashes of visited states
     let mut seen_hashes = HashSet::new();
 
-    for &(move_row, move_col) in &moves {
-        game.transition(move_row, move_col);
-
-        // Compute a hash of the current state without cloning.
-        let hash = calculate_hash(game.get_state());
+    for game_move in &moves {
+        game.transition(game_move);
 
         // Check if the hash of the current state has already been seen at this layer.
-        if seen_hashes.contains(&hash) {
+        if !seen_hashes.insert(game.get_hash()) {
             // If it has been visited, skip this move.
+            ctx.stats.duplicates_skipped += 1;
             game.undo_transition();
             continue;
         }
 
-        // If it hasn't been visited, add the hash to the HashSet and proceed with the search.
-        seen_hashes.insert(hash);
-
-        let (score, _) = dfs(game, d + 1, alphas, eval_func, search_depth);
-        if best_score.is_none() || score[player_idx] > best_score.as_ref().unwrap()[player_idx] {
-            best_score = Some(score);
-            best_move = Some((move_row, move_col));
-            if can_prune(best_score.as_ref().unwrap(), &alphas, player_idx) {
+        let child = dfs(game, d + 1, ctx);
+        if ctx.budget.is_aborted() {
+            game.undo_transition();
+            break;
+        }
+        cutoff_depth = cutoff_depth.min(child.cutoff_depth);
+        solved &= child.solved;
+        if best_score.is_none()
+            || child.score[player_idx] > best_score.as_ref().unwrap()[player_idx]
+        {
+            best_score = Some(child.score);
+            principal_variation = child.principal_variation;
+            principal_variation.insert(0, game_move.clone());
+            if can_prune(best_score.as_ref().unwrap(), &ctx.alphas, player_idx) {
+                ctx.stats.prunes += 1;
+                cutoff_depth = cutoff_depth.min(prune_source_depth(ctx, player_idx));
+                if let Some(ordering) = ctx.ordering.as_deref_mut() {
+                    ordering.record_prune(game_move, d);
+                }
                 game.undo_transition();
                 break;
             }
-            alphas[player_idx] = alphas[player_idx].max(best_score.as_ref().unwrap()[player_idx]);
+            let best = best_score.as_ref().unwrap()[player_idx];
+            if best > ctx.alphas[player_idx] {
+                ctx.alphas[player_idx] = best;
+                ctx.alpha_depths[player_idx] = d;
+            }
         }
         game.undo_transition();
     }
-    alphas[player_idx] = old_alpha;
-    // return all 0s if there are no available moves yet the game is ongoing (which shouldn't happen)
-    (
-        best_score.unwrap_or_else(|| vec![0.0; game.get_num_players()]),
-        best_move,
-    )
+    ctx.alphas[player_idx] = old_alpha;
+    ctx.alpha_depths[player_idx] = old_alpha_depth;
+
+    // Return all 0s if the search was aborted before any move was searched
+    let best_score = best_score.unwrap_or_else(|| vec![0.0; game.get_num_players()]);
+    let best_move = principal_variation.first().cloned();
+    if let (Some(best_move), false) = (best_move, ctx.budget.is_aborted()) {
+        if let Some(ordering) = ctx.ordering.as_deref_mut() {
+            ordering.record_best_move(&best_move, player_idx, remaining_depth);
+        }
+        // Prunes based on alphas set at or below this node leave its score exact
+        ctx.tt.store(
+            game.get_hash(),
+            if solved {
+                SOLVED_DEPTH
+            } else {
+                remaining_depth
+            },
+            best_score.clone(),
+            Some(best_move),
+            cutoff_depth >= d,
+        );
+    }
+    NodeResult {
+        score: best_score,
+        principal_variation,
+        cutoff_depth,
+        solved,
+    }
 }
 
-fn can_prune(score: &Vec<f64>, alphas: &Vec<f64>, player_idx: usize) -> bool {
+fn can_prune(score: &[f64], alphas: &[f64], player_idx: usize) -> bool {
     let max_other_player = alphas
         .iter()
         .enumerate()
@@ -92,14 +387,174 @@ fn can_prune(score: &Vec<f64>, alphas: &Vec<f64>, player_idx: usize) -> bool {
     score[player_idx] > 1.0 - max_other_player
 }
 
-fn calculate_hash(game_state: &GameState) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    game_state.get_to_move().hash(&mut hasher);
-    for row in game_state.get_board() {
-        for cell in row {
-            cell.hash(&mut hasher);
+// The shallowest node that set one of the other players' alphas used by can_prune
+fn prune_source_depth(ctx: &SearchContext, player_idx: usize) -> usize {
+    ctx.alpha_depths
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != player_idx)
+        .map(|(_, &depth)| depth)
+        .min()
+        .unwrap_or(NO_CUTOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::test_support::{new_game, unlimited_limits, OPENINGS};
+
+    const TT_MEMORY_BYTES: usize = 1024 * 1024;
+
+    // Max^n without pruning, transposition table or move ordering
+    fn plain_maxn(game: &mut Game, depth: usize) -> Vec<f64> {
+        if depth == 0 || game.get_game_status() != GameStatus::Ongoing {
+            return game.get_score();
+        }
+        let player_idx = game.get_to_move().to_usize();
+        let mut best: Option<Vec<f64>> = None;
+        for game_move in game.get_valid_moves() {
+            game.transition(&game_move);
+            let score = plain_maxn(game, depth - 1);
+            game.undo_transition();
+            if best
+                .as_ref()
+                .is_none_or(|best| score[player_idx] > best[player_idx])
+            {
+                best = Some(score);
+            }
+        }
+        best.unwrap_or_else(|| game.get_score())
+    }
+
+    #[test]
+    fn transposition_table_search_matches_plain_maxn() {
+        for num_players in 2..=3 {
+            for opening in OPENINGS {
+                let mut game = new_game(num_players, opening);
+                let player_idx = game.get_to_move().to_usize();
+                // Searching twice with the same table also checks that stored entries are reused
+                // correctly
+                let mut tt = TranspositionTable::new(TT_MEMORY_BYTES, num_players);
+                for depth in 1..=3 {
+                    let expected = plain_maxn(&mut game, depth);
+                    for _ in 0..2 {
+                        let result = minimax_move(
+                            &mut game,
+                            depth,
+                            &mut tt,
+                            Some(&mut MoveOrdering::default()),
+                        );
+                        assert_eq!(result.score[player_idx], expected[player_idx]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn iterative_deepening_matches_fixed_depth_search() {
+        for num_players in 2..=3 {
+            let mut game = new_game(num_players, &[3, 3, 2]);
+            let player_idx = game.get_to_move().to_usize();
+            for max_depth in 1..=3 {
+                let limits = unlimited_limits(max_depth, true);
+                let mut tt = TranspositionTable::new(TT_MEMORY_BYTES, num_players);
+                let result = iterative_deepening_move(
+                    &mut game,
+                    &limits,
+                    &mut tt,
+                    Some(&mut MoveOrdering::default()),
+                );
+                assert_eq!(result.depth, max_depth);
+                let expected = plain_maxn(&mut game, max_depth);
+                assert_eq!(result.score[player_idx], expected[player_idx]);
+            }
+        }
+    }
+
+    #[test]
+    fn iterative_deepening_stops_at_the_node_budget_with_a_move() {
+        let mut game = new_game(3, &[]);
+        let limits = SearchLimits {
+            time_budget_ms: None,
+            node_budget: Some(500),
+            max_depth: 64,
+            move_ordering: true,
+        };
+        let mut tt = TranspositionTable::new(TT_MEMORY_BYTES, 3);
+        let result = iterative_deepening_move(
+            &mut game,
+            &limits,
+            &mut tt,
+            Some(&mut MoveOrdering::default()),
+        );
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1 && result.depth < 64);
+        // The budget is checked at every node, so the last iteration stops right at it
+        assert!(result.nodes <= 500);
+    }
+
+    // With more players max^n can rarely prune whatever the order, so only two are compared
+    #[test]
+    fn move_ordering_reduces_nodes() {
+        let mut nodes = [0; 2];
+        for opening in OPENINGS {
+            let mut game = new_game(2, opening);
+            for (move_ordering, nodes) in [false, true].into_iter().zip(nodes.iter_mut()) {
+                let limits = unlimited_limits(4, move_ordering);
+                let tt = Rc::new(RefCell::new(TranspositionTable::new(TT_MEMORY_BYTES, 2)));
+                *nodes += MaxnSearcher::new(tt).search(&mut game, &limits).nodes;
+            }
+        }
+        assert!(nodes[1] < nodes[0]);
+    }
+
+    #[test]
+    fn analyze_moves_scores_every_move_with_plain_maxn() {
+        for num_players in 2..=3 {
+            for opening in OPENINGS {
+                let mut game = new_game(num_players, opening);
+                let mut tt = TranspositionTable::new(TT_MEMORY_BYTES, num_players);
+                let depth = 3;
+                let analyses = analyze_moves(
+                    &mut game,
+                    depth,
+                    &mut tt,
+                    Some(&mut MoveOrdering::default()),
+                );
+                let valid_moves = game.get_valid_moves();
+                assert_eq!(analyses.len(), valid_moves.len());
+                for (analysis, game_move) in analyses.iter().zip(&valid_moves) {
+                    assert_eq!(&analysis.move_made, game_move);
+                    game.transition(game_move);
+                    let expected = plain_maxn(&mut game, depth - 1);
+                    game.undo_transition();
+                    assert_eq!(analysis.score, expected);
+                }
+            }
         }
     }
-    // Do not include prev_state or move_num in the hash.
-    hasher.finish()
-}
\ No newline at end of file
+
+    #[test]
+    fn principal_variation_leads_to_the_reported_score() {
+        for num_players in 2..=3 {
+            let mut game = new_game(num_players, &[3, 3, 2]);
+            let mut tt = TranspositionTable::new(TT_MEMORY_BYTES, num_players);
+            let result = minimax_move(&mut game, 3, &mut tt, Some(&mut MoveOrdering::default()));
+            assert_eq!(result.depth, 3);
+            assert_eq!(result.principal_variation.len(), 3);
+            assert_eq!(
+                result.best_move.as_ref(),
+                result.principal_variation.first()
+            );
+            assert!(result.leaf_evaluations > 0 && result.leaf_evaluations < result.nodes);
+            assert_eq!(result.max_depth_reached, 3);
+
+            for game_move in &result.principal_variation {
+                assert!(game.get_valid_moves().contains(game_move));
+                game.transition(game_move);
+            }
+            assert_eq!(game.get_score(), result.score);
+        }
+    }
+}
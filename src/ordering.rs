@@ -0,0 +1,92 @@
+use std::cmp::Reverse;
+
+use crate::game::Game;
+use crate::game_elements::Move;
+
+// Killer moves remembered per depth
+const NUM_KILLERS: usize = 2;
+
+// Move ordering state for the max^n search, kept across searches so that history carries over
+#[derive(Default)]
+pub struct MoveOrdering {
+    num_cols: usize,
+    num_players: usize,
+    // Moves that caused a prune, per depth of the node they were played at
+    killers: Vec<[Option<Move>; NUM_KILLERS]>,
+    // Indexed by (row * num_cols + col) * num_players + player, with passes after all cells
+    history: Vec<u64>,
+}
+
+impl MoveOrdering {
+    // Called at the start of every search: killers are position specific so they are dropped,
+    // while history is halved so that older searches count for less
+    pub fn new_search(&mut self, game: &Game) {
+        let num_rows = game.get_num_rows();
+        let num_cols = game.get_num_cols();
+        let num_players = game.get_num_players();
+        let history_len = (num_rows * num_cols + 1) * num_players;
+        if self.num_cols != num_cols
+            || self.num_players != num_players
+            || self.history.len() != history_len
+        {
+            self.num_cols = num_cols;
+            self.num_players = num_players;
+            self.history = vec![0; history_len];
+        }
+        self.history.iter_mut().for_each(|score| *score /= 2);
+        self.killers.clear();
+    }
+
+    // Sorts moves so that the transposition table move comes first, then killers,
+    // then moves by the spec's hint and finally by history
+    pub fn order_moves(&self, game: &Game, moves: &mut [Move], d: usize, tt_move: Option<&Move>) {
+        let player_idx = game.get_to_move().to_usize();
+        let killers = self.killers.get(d);
+        moves.sort_by_cached_key(|game_move| {
+            let class = if Some(game_move) == tt_move {
+                NUM_KILLERS + 1
+            } else {
+                killers
+                    .and_then(|killers| killers.iter().position(|k| k.as_ref() == Some(game_move)))
+                    .map_or(0, |slot| NUM_KILLERS - slot)
+            };
+            (
+                Reverse(class),
+                Reverse(game.get_move_ordering_hint(game_move)),
+                Reverse(self.history[self.history_index(game_move, player_idx)]),
+            )
+        });
+    }
+
+    // Rewards the best move found at a node, weighted by how much was searched below it
+    pub fn record_best_move(
+        &mut self,
+        best_move: &Move,
+        player_idx: usize,
+        remaining_depth: usize,
+    ) {
+        let index = self.history_index(best_move, player_idx);
+        let bonus = (remaining_depth * remaining_depth) as u64;
+        self.history[index] = self.history[index].saturating_add(bonus);
+    }
+
+    pub fn record_prune(&mut self, prune_move: &Move, d: usize) {
+        if self.killers.len() <= d {
+            self.killers.resize(d + 1, Default::default());
+        }
+        let killers = &mut self.killers[d];
+        if killers[0].as_ref() != Some(prune_move) {
+            killers.rotate_right(1);
+            killers[0] = Some(prune_move.clone());
+        }
+    }
+
+    // Moves share a slot by destination, which is enough to tell most moves apart
+    fn history_index(&self, game_move: &Move, player_idx: usize) -> usize {
+        let slot = match game_move.get_destination() {
+            Some((move_row, move_col)) => move_row * self.num_cols + move_col,
+            None => self.history.len() / self.num_players - 1,
+        };
+        slot * self.num_players + player_idx
+    }
+}
@@ -1,70 +1,68 @@
-use crate::game::{Game, GameState};
-use crate::game_elements::{BoardCell, Player};
-use crate::game_spec::GameSpec;
+use crate::board::{Board, DIRECTIONS};
+use crate::config::{self, ConfigParam, ConfigSchema, GameConfig};
+use crate::eval::{EvaluationFunction, OthelloEvaluationFunction};
+use crate::game::{Game, MoveDelta};
+use crate::game_elements::{BoardCell, Move, Outcome, Player};
+use crate::game_spec::{GameSpec, PassPolicy};
+use crate::metadata::{CoordinateStyle, GameMetadata, MoveStyle, PlayerStyle};
+
+// Default board size, which can be changed through GameConfig to any even size in the limits
+const NUM_ROWS: usize = 8;
+const NUM_COLS: usize = 8;
+const MIN_BOARD_SIZE: i64 = 4;
+const MAX_BOARD_SIZE: i64 = 16;
+
+// Starting discs for 2, 3 and 4 players, as (row, col, player) from the top left of the four
+// center cells. Two players start on the usual diagonals, three on a 2x3 block where each row
+// cycles through the players, and four with one disc each as in Rolit.
+const STARTING_LAYOUTS: [&[(usize, usize, usize)]; 3] = [
+    &[(0, 0, 0), (0, 1, 1), (1, 0, 1), (1, 1, 0)],
+    &[
+        (0, 0, 0),
+        (0, 1, 1),
+        (0, 2, 2),
+        (1, 0, 2),
+        (1, 1, 0),
+        (1, 2, 1),
+    ],
+    &[(0, 0, 0), (0, 1, 1), (1, 1, 2), (1, 0, 3)],
+];
 
 pub struct OthelloSpec;
 
 impl OthelloSpec {
-    fn flip_discs(board: &mut Vec<Vec<BoardCell>>, row: usize, col: usize, player: Player) {
-        let directions = [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ];
-
-        for dir in directions.iter() {
-            let mut r = row as i32 + dir.0;
-            let mut c = col as i32 + dir.1;
-
+    fn flip_discs(board: &Board, row: usize, col: usize, player: Player, delta: &mut MoveDelta) {
+        for &direction in DIRECTIONS.iter() {
             let mut pieces_to_flip = Vec::new();
 
-            while r >= 0 && r < 8 && c >= 0 && c < 8 {
-                match board[r as usize][c as usize] {
-                    Some(p) if p != player => pieces_to_flip.push((r as usize, c as usize)),
+            for (r, c, cell) in board.ray(row, col, direction) {
+                match cell.get_owner() {
+                    Some(p) if p != player => pieces_to_flip.push((r, c)),
                     Some(p) if p == player => {
                         for &(flip_r, flip_c) in &pieces_to_flip {
-                            board[flip_r][flip_c] = Some(player);
+                            delta
+                                .changed_cells
+                                .push((flip_r, flip_c, BoardCell::owned_by(player)));
                         }
                         break;
                     }
                     _ => break,
                 }
-
-                r += dir.0;
-                c += dir.1;
             }
         }
     }
 
-    fn is_valid_move(board: &Vec<Vec<BoardCell>>, row: usize, col: usize, player: Player) -> bool {
-        if board[row][col].is_some() {
+    // A move is valid if it sandwiches a line of other players' discs against one of the mover's
+    pub(crate) fn is_valid_move(board: &Board, row: usize, col: usize, player: Player) -> bool {
+        if !board[(row, col)].is_empty() {
             return false;
         }
 
-        let directions = [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ];
-
-        for dir in directions.iter() {
-            let mut r = row as i32 + dir.0;
-            let mut c = col as i32 + dir.1;
-
+        for &direction in DIRECTIONS.iter() {
             let mut has_opponent_between = false;
 
-            while r >= 0 && r < 8 && c >= 0 && c < 8 {
-                match board[r as usize][c as usize] {
+            for (_, _, cell) in board.ray(row, col, direction) {
+                match cell.get_owner() {
                     Some(p) if p != player => has_opponent_between = true,
                     Some(p) if p == player => {
                         if has_opponent_between {
@@ -75,76 +73,299 @@ impl OthelloSpec {
                     }
                     _ => break,
                 }
-
-                r += dir.0;
-                c += dir.1;
             }
         }
 
         false
     }
 
+    // The game ends once no player has a disc to place
+    fn is_game_over(game: &Game) -> bool {
+        let board = game.get_board();
+        !(0..game.get_num_players()).any(|player| {
+            board
+                .cells()
+                .any(|(row, col, _)| Self::is_valid_move(board, row, col, Player(player)))
+        })
+    }
+
     fn get_player_disc_count(&self, game: &Game, player: Player) -> usize {
         game.get_board()
-            .iter()
-            .flatten()
-            .filter(|&cell| *cell == Some(player))
+            .cells()
+            .filter(|(_, _, cell)| cell.is_owned_by(player))
             .count()
     }
 }
 
 impl GameSpec for OthelloSpec {
-    fn get_initial_board(&self, _num_rows: usize, _num_cols: usize) -> Vec<Vec<BoardCell>> {
-        let mut board = vec![vec![None; 8]; 8];
-        board[3][3] = Some(Player::Player0);
-        board[4][4] = Some(Player::Player0);
-        board[3][4] = Some(Player::Player1);
-        board[4][3] = Some(Player::Player1);
+    fn get_initial_board(&self, config: &GameConfig, num_players: usize) -> Board {
+        let num_rows = config.num_rows.unwrap_or(NUM_ROWS);
+        let num_cols = config.num_cols.unwrap_or(NUM_COLS);
+        let mut board = Board::new(num_rows, num_cols);
+        let (center_row, center_col) = (num_rows / 2 - 1, num_cols / 2 - 1);
+        for &(row, col, player) in STARTING_LAYOUTS[num_players - 2] {
+            board[(center_row + row, center_col + col)] = BoardCell::owned_by(Player(player));
+        }
         board
     }
 
     fn get_initial_to_move(&self) -> Player {
-        Player::Player0
+        Player(0)
     }
 
-    fn get_valid_moves(&self, game: &Game) -> Vec<(usize, usize)> {
+    fn get_valid_moves(&self, game: &Game) -> Vec<Move> {
         let mut valid_moves = Vec::new();
-        for row in 0..8 {
-            for col in 0..8 {
+        for row in 0..game.get_num_rows() {
+            for col in 0..game.get_num_cols() {
                 if Self::is_valid_move(game.get_board(), row, col, game.get_to_move()) {
-                    valid_moves.push((row, col));
+                    valid_moves.push(Move::place(row, col));
                 }
             }
         }
         valid_moves
     }
 
-    fn transition(&self, game: &Game, move_row: usize, move_col: usize) -> GameState {
-        let mut board_copy = game.get_board().clone();
-        board_copy[move_row][move_col] = Some(game.get_to_move());
-        Self::flip_discs(&mut board_copy, move_row, move_col, game.get_to_move());
-        GameState::new(game.get_next_player(), game.get_move_num() + 1, board_copy)
+    fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta {
+        let (move_row, move_col) = game_move
+            .get_destination()
+            .expect("Discs are always placed");
+        let mut delta = MoveDelta {
+            changed_cells: vec![(move_row, move_col, BoardCell::owned_by(game.get_to_move()))],
+            next_to_move: game.get_next_player(),
+        };
+        Self::flip_discs(
+            game.get_board(),
+            move_row,
+            move_col,
+            game.get_to_move(),
+            &mut delta,
+        );
+        delta
     }
 
     fn is_win(&self, game: &Game, player: Player) -> bool {
-        // Win if opponent has no valid moves and the current player has more discs
-        let opponent = if player == Player::Player0 {
-            Player::Player1
-        } else {
-            Player::Player0
-        };
-        if self.get_valid_moves(game).is_empty() && self.get_player_disc_count(game, player) > self
-            .get_player_disc_count(game, opponent)
-        {
-            return true;
+        // Win if nobody can move and the player has strictly the most discs
+        if !Self::is_game_over(game) {
+            return false;
         }
-        false
+        let disc_count = self.get_player_disc_count(game, player);
+        (0..game.get_num_players())
+            .map(Player)
+            .filter(|&other| other != player)
+            .all(|other| self.get_player_disc_count(game, other) < disc_count)
     }
 
     fn is_tie(&self, game: &Game) -> bool {
-        // Tie if neither player can move
-        self.get_valid_moves(game).is_empty()
-            && self.get_player_disc_count(game, Player::Player0)
-                == self.get_player_disc_count(game, Player::Player1)
+        // Tie if nobody can move and the most discs are shared
+        if !Self::is_game_over(game) {
+            return false;
+        }
+        let disc_counts: Vec<usize> = (0..game.get_num_players())
+            .map(|player| self.get_player_disc_count(game, Player(player)))
+            .collect();
+        let most = disc_counts.iter().copied().max().unwrap_or(0);
+        disc_counts.iter().filter(|&&count| count == most).count() > 1
+    }
+
+    // With more than two players, everyone is ranked by their discs, sharing places on equal
+    // counts
+    fn get_outcome(&self, game: &Game) -> Option<Outcome> {
+        if game.get_num_players() <= 2 || !Self::is_game_over(game) {
+            return None;
+        }
+        let disc_counts: Vec<usize> = (0..game.get_num_players())
+            .map(|player| self.get_player_disc_count(game, Player(player)))
+            .collect();
+        let placements = disc_counts
+            .iter()
+            .map(|&count| disc_counts.iter().filter(|&&other| other > count).count())
+            .collect();
+        Some(Outcome::Placements(placements))
+    }
+
+    // A player without a placement has to pass, and play continues until nobody can place
+    fn get_pass_policy(&self) -> PassPolicy {
+        PassPolicy::WhenForced
+    }
+
+    fn get_config_schema(&self) -> ConfigSchema {
+        ConfigSchema {
+            params: vec![
+                ConfigParam::new(
+                    config::NUM_ROWS,
+                    NUM_ROWS as i64,
+                    MIN_BOARD_SIZE,
+                    MAX_BOARD_SIZE,
+                )
+                .with_step(2),
+                ConfigParam::new(
+                    config::NUM_COLS,
+                    NUM_COLS as i64,
+                    MIN_BOARD_SIZE,
+                    MAX_BOARD_SIZE,
+                )
+                .with_step(2),
+            ],
+        }
+    }
+
+    fn get_evaluation_function(&self, game: &Game) -> Box<dyn EvaluationFunction> {
+        Box::new(OthelloEvaluationFunction::new(game.get_num_players()))
+    }
+
+    fn get_metadata(&self, _config: &GameConfig) -> GameMetadata {
+        GameMetadata {
+            name: "Othello".to_string(),
+            rules: "Place a disc so that it sandwiches a line of other players' discs against \
+                    one of your own, then flip the sandwiched discs to your color. Pass when you \
+                    can't place. Once nobody can move, players are ranked by their discs."
+                .to_string(),
+            min_players: 2,
+            max_players: STARTING_LAYOUTS.len() + 1,
+            player_styles: vec![
+                PlayerStyle::new("●", "#000000"),
+                PlayerStyle::new("○", "#ffffff"),
+                PlayerStyle::new("▲", "#e53935"),
+                PlayerStyle::new("■", "#1e88e5"),
+            ],
+            coordinate_style: CoordinateStyle::Algebraic,
+            move_style: MoveStyle::Placement,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::GameError;
+    use crate::game_elements::GameStatus;
+
+    fn new_game(num_players: usize, size: Option<usize>) -> Result<Game, GameError> {
+        let config = GameConfig {
+            num_rows: size,
+            num_cols: size,
+            ..GameConfig::default()
+        };
+        Game::new(Box::new(OthelloSpec), num_players, &config)
+    }
+
+    // How many discs each player has on the board
+    fn disc_counts(game: &Game) -> Vec<usize> {
+        (0..game.get_num_players())
+            .map(|player| OthelloSpec.get_player_disc_count(game, Player(player)))
+            .collect()
+    }
+
+    // Replaces the whole board, with one string per row holding a player's digit or '.' for an
+    // empty cell
+    fn set_board(game: &mut Game, rows: &[&str]) {
+        let cells = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| {
+                cells.chars().enumerate().map(move |(col, cell)| {
+                    let cell = match cell.to_digit(10) {
+                        Some(player) => BoardCell::owned_by(Player(player as usize)),
+                        None => BoardCell::Empty,
+                    };
+                    (row, col, cell)
+                })
+            })
+            .collect();
+        game.set_cells(cells);
+    }
+
+    #[test]
+    fn two_players_start_on_the_diagonals() {
+        let game = new_game(2, None).unwrap();
+        let board = game.get_board();
+        assert!(board[(3, 3)].is_owned_by(Player(0)) && board[(4, 4)].is_owned_by(Player(0)));
+        assert!(board[(3, 4)].is_owned_by(Player(1)) && board[(4, 3)].is_owned_by(Player(1)));
+        assert_eq!(disc_counts(&game), vec![2, 2]);
+        assert_eq!(game.get_valid_moves().len(), 4);
+    }
+
+    #[test]
+    fn three_and_four_players_each_get_discs_and_moves() {
+        for (num_players, discs_each) in [(3, 2), (4, 1)] {
+            let mut game = new_game(num_players, None).unwrap();
+            assert_eq!(disc_counts(&game), vec![discs_each; num_players]);
+            // Every player can place a disc in the opening
+            for player in 0..num_players {
+                assert_eq!(game.get_to_move(), Player(player));
+                let game_move = game.get_valid_moves()[0].clone();
+                game.make_move(&game_move).unwrap();
+            }
+            assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        }
+    }
+
+    #[test]
+    fn boards_can_be_any_even_size_in_range() {
+        for size in [4, 6, 10, 16] {
+            let game = new_game(2, Some(size)).unwrap();
+            assert_eq!(game.get_num_rows(), size);
+            // The starting discs stay in the center
+            let center = size / 2 - 1;
+            assert!(game.get_board()[(center, center)].is_owned_by(Player(0)));
+        }
+        for size in [5, 7, 2, 18] {
+            assert!(matches!(
+                new_game(2, Some(size)),
+                Err(GameError::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn player_counts_outside_two_to_four_are_rejected() {
+        for num_players in [1, 5] {
+            assert!(matches!(
+                new_game(num_players, None),
+                Err(GameError::BadPlayerCount { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn a_player_without_a_placement_has_to_pass() {
+        let mut game = new_game(2, Some(4)).unwrap();
+        set_board(&mut game, &["10..", "....", "....", "...."]);
+        assert_eq!(game.get_valid_moves(), vec![Move::Pass]);
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        game.make_move(&Move::Pass).unwrap();
+        assert_eq!(game.get_to_move(), Player(1));
+        assert_eq!(game.get_valid_moves(), vec![Move::place(0, 2)]);
+    }
+
+    #[test]
+    fn the_game_ends_when_nobody_can_place() {
+        let mut game = new_game(2, Some(4)).unwrap();
+        set_board(&mut game, &["00..", "....", "....", "...1"]);
+        assert!(OthelloSpec::is_game_over(&game));
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(0)));
+
+        set_board(&mut game, &["00..", "....", "....", "..11"]);
+        assert!(OthelloSpec::is_game_over(&game));
+        assert_eq!(game.get_game_status(), GameStatus::Tie);
+    }
+
+    #[test]
+    fn more_players_are_ranked_by_their_discs() {
+        let mut game = new_game(4, Some(4)).unwrap();
+        set_board(&mut game, &["00..", "....", "....", "2.11"]);
+        assert!(OthelloSpec::is_game_over(&game));
+        // Players 0 and 1 share first place, and player 3 has no discs left
+        assert_eq!(
+            game.get_game_status(),
+            GameStatus::Over(Outcome::Placements(vec![0, 0, 2, 3]))
+        );
+
+        let mut game = new_game(3, Some(4)).unwrap();
+        set_board(&mut game, &["0...", "....", "....", "2.11"]);
+        assert_eq!(
+            game.get_game_status(),
+            GameStatus::Over(Outcome::Placements(vec![1, 0, 1]))
+        );
     }
 }
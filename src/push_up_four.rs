@@ -3,136 +3,168 @@
 // ValidMoves: Players can place chips on the bottom row of columns that aren't full.
 // TransitionFunction: Chips are placed at the bottom of the column, pushing other chips up.
 // WinCondition: There are n consecutive chips in a row, column, or diagonal.
-// TieCondition: Either more than one player wins simultaneously or the board fills up.
+// TieCondition: The board fills up. Players who get n in a row simultaneously share the win.
 
-use crate::game::{Game, GameState};
-use crate::game_elements::{BoardCell, Player};
+use crate::board::Board;
+use crate::config::{self, ConfigParam, ConfigSchema, GameConfig};
+use crate::eval::{EvaluationFunction, PushUpFourEvaluationFunction};
+use crate::game::{Game, MoveDelta};
+use crate::game_elements::{BoardCell, Move, Outcome, Player};
 use crate::game_spec::GameSpec;
+use crate::metadata::{GameMetadata, MoveStyle};
 
+// Defaults, all of which can be changed through GameConfig
 const N_IN_A_ROW: usize = 4;
+const NUM_ROWS: usize = 6;
+const NUM_COLS: usize = 7;
+
+// Limits on the configurable board size
+const MIN_BOARD_SIZE: i64 = 3;
+const MAX_BOARD_SIZE: i64 = 16;
 
 pub struct PushUpFourSpec;
 impl GameSpec for PushUpFourSpec {
-    fn get_initial_board(&self, rows: usize, cols: usize) -> Vec<Vec<BoardCell>> {
-        vec![vec![None; cols]; rows]
+    fn get_initial_board(&self, config: &GameConfig, _num_players: usize) -> Board {
+        Board::new(
+            config.num_rows.unwrap_or(NUM_ROWS),
+            config.num_cols.unwrap_or(NUM_COLS),
+        )
     }
 
     fn get_initial_to_move(&self) -> Player {
-        Player::Player0
+        Player(0)
     }
 
-    fn get_valid_moves(&self, game: &Game) -> Vec<(usize, usize)> {
+    fn get_valid_moves(&self, game: &Game) -> Vec<Move> {
         let bottom_row = game.get_num_rows() - 1;
         (0..game.get_num_cols())
             .filter_map(|col| {
-                match game.get_board()[0][col] {
-                    None => Some((bottom_row, col)), // (row, col) with origin at the top left
+                match game.get_board()[(0, col)] {
+                    BoardCell::Empty => Some(Move::place(bottom_row, col)), // (row, col) with origin at the top left
                     _ => None,
                 }
             })
             .collect()
     }
 
-    fn transition(&self, game: &Game, _move_row: usize, move_col: usize) -> GameState {
-        let mut board_copy = game.get_board().clone();
+    fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta {
+        let board = game.get_board();
+        let (_, move_col) = game_move
+            .get_destination()
+            .expect("Chips are always placed");
 
         // Push the new chip up the bottom, shifting other chips up
-        for row in 0..game.get_num_rows() - 1 {
-            board_copy[row][move_col] = board_copy[row + 1][move_col];
+        let mut changed_cells: Vec<(usize, usize, BoardCell)> = (0..game.get_num_rows() - 1)
+            .filter(|&row| board[(row, move_col)] != board[(row + 1, move_col)])
+            .map(|row| (row, move_col, board[(row + 1, move_col)].clone()))
+            .collect();
+        changed_cells.push((
+            game.get_num_rows() - 1,
+            move_col,
+            BoardCell::owned_by(game.get_to_move()),
+        ));
+
+        // Update the player to move along with the cells
+        MoveDelta {
+            changed_cells,
+            next_to_move: game.get_next_player(),
         }
-        board_copy[game.get_num_rows() - 1][move_col] = Some(game.get_to_move());
-
-        // Update the player to move, the move number, and the state of the baord
-        GameState::new(game.get_next_player(), game.get_move_num() + 1, board_copy)
     }
 
     fn is_win(&self, game: &Game, player: Player) -> bool {
-        self.is_row_win(game, player)
-            || self.is_col_win(game, player)
-            || self.is_diag_win(game, player)
+        let board = game.get_board();
+        board.lines(Self::get_win_length(game)).any(|line| {
+            line.iter()
+                .all(|&position| board[position].is_owned_by(player))
+        })
     }
 
+    // The board filling up only draws if the last push didn't complete a line
     fn is_tie(&self, game: &Game) -> bool {
         game.get_move_num() == game.get_num_rows() * game.get_num_cols()
-            || self.is_tie_after_transition(game)
+            && !(0..game.get_num_players()).any(|player| self.is_win(game, Player(player)))
     }
-}
 
-impl PushUpFourSpec {
-    fn is_sequence_win(&self, sequence: &[BoardCell], player: Player) -> bool {
-        sequence
+    // A push can complete lines for several players at once, who then draw for first place
+    // while everyone else loses
+    fn get_outcome(&self, game: &Game) -> Option<Outcome> {
+        let winners: Vec<bool> = (0..game.get_num_players())
+            .map(|player| self.is_win(game, Player(player)))
+            .collect();
+        if winners.iter().filter(|&&x| x).count() <= 1 {
+            return None;
+        }
+        let placements = winners
             .iter()
-            .filter(|&cell| *cell == Some(player))
-            .count()
-            >= N_IN_A_ROW
+            .map(|&is_winner| if is_winner { 0 } else { 1 })
+            .collect();
+        Some(Outcome::Placements(placements))
     }
 
-    fn is_row_win(&self, game: &Game, player: Player) -> bool {
-        let num_cols = game.get_num_cols();
-        for row in game.get_state().get_board().iter() {
-            for i in 0..num_cols - N_IN_A_ROW + 1 {
-                if self.is_sequence_win(&row[i..i + N_IN_A_ROW], player) {
-                    return true;
-                }
-            }
-        }
-        false
+    fn get_evaluation_function(&self, game: &Game) -> Box<dyn EvaluationFunction> {
+        Box::new(PushUpFourEvaluationFunction::new(
+            game.get_num_players(),
+            Self::get_win_length(game),
+        ))
     }
 
-    fn is_col_win(&self, game: &Game, player: Player) -> bool {
-        let num_cols = game.get_num_cols();
-        let num_rows = game.get_num_rows();
-        for col in 0..num_cols {
-            let col_elems: Vec<BoardCell> = game
-                .get_state()
-                .get_board()
-                .iter()
-                .map(|row| row[col])
-                .collect();
-            for i in 0..num_rows - N_IN_A_ROW + 1 {
-                if self.is_sequence_win(&col_elems[i..i + N_IN_A_ROW], player) {
-                    return true;
-                }
-            }
+    fn get_config_schema(&self) -> ConfigSchema {
+        ConfigSchema {
+            params: vec![
+                ConfigParam::new(
+                    config::NUM_ROWS,
+                    NUM_ROWS as i64,
+                    MIN_BOARD_SIZE,
+                    MAX_BOARD_SIZE,
+                ),
+                ConfigParam::new(
+                    config::NUM_COLS,
+                    NUM_COLS as i64,
+                    MIN_BOARD_SIZE,
+                    MAX_BOARD_SIZE,
+                ),
+                ConfigParam::new(config::WIN_LENGTH, N_IN_A_ROW as i64, 2, MAX_BOARD_SIZE),
+            ],
         }
-        false
     }
 
-    fn is_diag_win(&self, game: &Game, player: Player) -> bool {
-        let num_rows = game.get_num_rows();
-        let num_cols = game.get_num_cols();
-
-        // Check main diagonals
-        for i in 0..num_rows - N_IN_A_ROW + 1 {
-            for j in 0..num_cols - N_IN_A_ROW + 1 {
-                let diagonal: Vec<BoardCell> = (0..N_IN_A_ROW)
-                    .map(|k| game.get_state().get_board()[i + k][j + k])
-                    .collect();
-                if self.is_sequence_win(&diagonal, player) {
-                    return true;
-                }
-            }
+    fn get_metadata(&self, config: &GameConfig) -> GameMetadata {
+        GameMetadata {
+            name: "Push Up Four".to_string(),
+            rules: format!(
+                "Push a chip into the bottom of a column that isn't full, shifting the chips \
+                 above it up. The first to get {} in a row, column or diagonal wins, and players \
+                 who get there with the same push share the win.",
+                config.win_length.unwrap_or(N_IN_A_ROW)
+            ),
+            min_players: 2,
+            move_style: MoveStyle::Gravity,
+            ..GameMetadata::default()
         }
+    }
+}
 
-        // Check counter-diagonals
-        for i in 0..num_rows - N_IN_A_ROW + 1 {
-            for j in N_IN_A_ROW - 1..num_cols {
-                let diagonal: Vec<BoardCell> = (0..N_IN_A_ROW)
-                    .map(|k| game.get_state().get_board()[i + k][j - k])
-                    .collect();
-                if self.is_sequence_win(&diagonal, player) {
-                    return true;
-                }
-            }
-        }
-        false
+impl PushUpFourSpec {
+    fn get_win_length(game: &Game) -> usize {
+        game.get_config().win_length.unwrap_or(N_IN_A_ROW)
     }
+}
 
-    fn is_tie_after_transition(&self, game: &Game) -> bool {
-        let num_players = game.get_num_players();
-        let winners: Vec<bool> = (0..num_players)
-            .map(|player| self.is_win(game, Player::from(player)))
-            .collect();
-        winners.iter().filter(|&&x| x).count() > 1
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_name_the_configured_win_length() {
+        let rules = |win_length| {
+            let config = GameConfig {
+                win_length,
+                ..GameConfig::default()
+            };
+            let game = Game::new(Box::new(PushUpFourSpec), 2, &config).unwrap();
+            game.get_metadata().rules
+        };
+        assert!(rules(None).contains("get 4 in a row"));
+        assert!(rules(Some(5)).contains("get 5 in a row"));
     }
 }
@@ -0,0 +1,86 @@
+// The games a GameController can be created for, looked up by name
+
+use serde::Serialize;
+
+use crate::config::{ConfigSchema, GameConfig};
+use crate::error::GameError;
+use crate::game::Game;
+use crate::game_spec::GameSpec;
+use crate::metadata::GameMetadata;
+use crate::othello::OthelloSpec;
+use crate::push_up_four::PushUpFourSpec;
+use crate::user_game::UserGameSpec;
+
+// Name of the game generated from the user's description
+pub const USER_GAME: &str = "user_game";
+
+pub struct GameEntry {
+    // Identifies the game in create_game_controller_for
+    pub name: &'static str,
+    create_spec: fn() -> Box<dyn GameSpec>,
+}
+
+// What list_games tells JS about a game
+#[derive(Serialize)]
+pub struct GameListing {
+    pub name: &'static str,
+    pub metadata: GameMetadata,
+    // The config a game gets when none is given
+    pub default_config: GameConfig,
+    pub config_schema: ConfigSchema,
+}
+
+static GAMES: [GameEntry; 3] = [
+    GameEntry {
+        name: USER_GAME,
+        create_spec: || Box::new(UserGameSpec),
+    },
+    GameEntry {
+        name: "othello",
+        create_spec: || Box::new(OthelloSpec),
+    },
+    GameEntry {
+        name: "push_up_four",
+        create_spec: || Box::new(PushUpFourSpec),
+    },
+];
+
+pub fn get_games() -> &'static [GameEntry] {
+    &GAMES
+}
+
+pub fn find_game(name: &str) -> Result<&'static GameEntry, GameError> {
+    GAMES
+        .iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| GameError::UnknownGame(name.to_string()))
+}
+
+impl GameEntry {
+    // Fails if the game can't be played by that many players or the config doesn't fit
+    pub fn create_game(&self, num_players: usize, config: &GameConfig) -> Result<Game, GameError> {
+        Game::new(self.new_spec(), num_players, config)
+    }
+
+    pub fn new_spec(&self) -> Box<dyn GameSpec> {
+        (self.create_spec)()
+    }
+
+    pub fn get_config_schema(&self) -> ConfigSchema {
+        self.new_spec().get_config_schema()
+    }
+
+    pub fn get_listing(&self) -> Result<GameListing, GameError> {
+        let spec = self.new_spec();
+        let config_schema = spec.get_config_schema();
+        let default_config = GameConfig::default()
+            .resolve(&config_schema)
+            .map_err(GameError::InvalidConfig)?;
+        Ok(GameListing {
+            name: self.name,
+            metadata: spec.get_metadata(&default_config),
+            default_config,
+            config_schema,
+        })
+    }
+}
@@ -0,0 +1,875 @@
+// Plays any game described by a rule document, so that one build can load new games at runtime
+
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+
+use crate::board::Board;
+use crate::config::GameConfig;
+use crate::error::GameError;
+use crate::game::{Game, MoveDelta};
+use crate::game_elements::{BoardCell, Move, Outcome, Piece, PieceKind, Player};
+use crate::game_spec::{GameSpec, PassPolicy};
+use crate::metadata::{GameMetadata, MoveStyle};
+use crate::rules::{
+    CaptureRule, InitialPiece, MovePattern, RuleDocument, Sides, Target, WinCondition,
+};
+
+// The winners once the game is over, empty for a draw, or None while it goes on
+type GameResult = Option<Vec<Player>>;
+
+pub struct RuleSpec {
+    document: RuleDocument,
+    // The result of the last position asked about, by hash and move number, since the status
+    // of a position is checked once per player
+    last_result: RefCell<Option<(u64, usize, GameResult)>>,
+}
+
+// Offsets a position, or None when that steps off the board
+fn step(board: &Board, (row, col): (usize, usize), (dr, dc): (i32, i32)) -> Option<(usize, usize)> {
+    let (r, c) = (row as i32 + dr, col as i32 + dc);
+    board.in_bounds(r, c).then_some((r as usize, c as usize))
+}
+
+fn is_opponent(cell: &BoardCell, player: Player) -> bool {
+    cell.get_owner().is_some_and(|owner| owner != player)
+}
+
+// Writes a cell to the working copy of the board and records the change
+fn set_cell(board: &mut Board, delta: &mut MoveDelta, (row, col): (usize, usize), cell: BoardCell) {
+    board[(row, col)] = cell.clone();
+    delta.changed_cells.push((row, col, cell));
+}
+
+// Follows the jumps of one piece, for a single jump pattern
+struct JumpChain<'a> {
+    board: &'a Board,
+    player: Player,
+    steps: &'a [(i32, i32)],
+    over: Target,
+    chain: bool,
+    from: (usize, usize),
+    // Cells landed on and pieces jumped over so far along the chain
+    landings: Vec<(usize, usize)>,
+    jumped: Vec<(usize, usize)>,
+    moves: &'a mut Vec<Move>,
+}
+
+impl JumpChain<'_> {
+    // Extends the chain from `at`, adding a move wherever the chain has to stop
+    fn extend(&mut self, at: (usize, usize)) {
+        let board = self.board;
+        for &direction in self.steps {
+            let Some(between) = step(board, at, direction) else {
+                continue;
+            };
+            let Some(landing) = step(board, between, direction) else {
+                continue;
+            };
+            // The moving piece has left its cell, and each piece is jumped at most once
+            let lands = board[landing].is_empty() || landing == self.from;
+            if !lands
+                || self.jumped.contains(&between)
+                || !RuleSpec::can_jump(self.over, &board[between], self.player)
+            {
+                continue;
+            }
+            self.jumped.push(between);
+            let num_moves = self.moves.len();
+            if self.chain {
+                self.landings.push(landing);
+                self.extend(landing);
+                self.landings.pop();
+            }
+            if self.moves.len() == num_moves {
+                self.moves.push(Move::Play {
+                    from: Some(self.from),
+                    to: landing,
+                    piece: None,
+                    path: self.landings.clone(),
+                });
+            }
+            self.jumped.pop();
+        }
+    }
+}
+
+impl RuleSpec {
+    // Fails with the reason when the document isn't valid JSON or describes an unplayable game
+    pub fn from_json(json: &str) -> Result<RuleSpec, GameError> {
+        let document = RuleDocument::from_json(json).map_err(GameError::InvalidRules)?;
+        Ok(RuleSpec {
+            document,
+            last_result: RefCell::new(None),
+        })
+    }
+
+    fn piece_matches(kind: Option<PieceKind>, piece: Piece) -> bool {
+        kind.is_none_or(|kind| kind == piece.kind)
+    }
+
+    fn placed_piece(kind: PieceKind) -> Option<PieceKind> {
+        (kind != 0).then_some(kind)
+    }
+
+    // The cells the capture rules change when the player's piece arrives at `to`
+    fn find_captures(
+        &self,
+        board: &Board,
+        to: (usize, usize),
+        player: Player,
+    ) -> Vec<((usize, usize), BoardCell)> {
+        let mut captures = Vec::new();
+        for rule in &self.document.captures {
+            match rule {
+                CaptureRule::Flank { directions } => {
+                    for &direction in directions.get_steps() {
+                        let mut flanked = Vec::new();
+                        for (r, c, cell) in board.ray(to.0, to.1, direction) {
+                            if is_opponent(cell, player) {
+                                flanked.push((r, c));
+                                continue;
+                            }
+                            if cell.is_owned_by(player) {
+                                for &(flip_r, flip_c) in &flanked {
+                                    let kind = board[(flip_r, flip_c)].get_top().unwrap().kind;
+                                    let flipped = BoardCell::Piece(Piece {
+                                        owner: Some(player),
+                                        kind,
+                                    });
+                                    captures.push(((flip_r, flip_c), flipped));
+                                }
+                            }
+                            break;
+                        }
+                    }
+                }
+                CaptureRule::Custodian { directions } => {
+                    for &direction in directions.get_steps() {
+                        let Some(between) = step(board, to, direction) else {
+                            continue;
+                        };
+                        let Some(beyond) = step(board, between, direction) else {
+                            continue;
+                        };
+                        if is_opponent(&board[between], player) && board[beyond].is_owned_by(player)
+                        {
+                            captures.push((between, BoardCell::Empty));
+                        }
+                    }
+                }
+            }
+        }
+        captures
+    }
+
+    // Whether some jump pattern the moving piece follows takes the piece jumped over
+    fn captures_jumped(&self, moving: Piece, jumped: &BoardCell, player: Player) -> bool {
+        self.document.moves.iter().any(|pattern| match *pattern {
+            MovePattern::Jump {
+                piece,
+                over,
+                capture,
+                ..
+            } => {
+                capture
+                    && Self::piece_matches(piece, moving)
+                    && Self::can_jump(over, jumped, player)
+            }
+            _ => false,
+        })
+    }
+
+    fn can_jump(over: Target, cell: &BoardCell, player: Player) -> bool {
+        match over {
+            Target::Opponent => is_opponent(cell, player),
+            Target::Own => cell.is_owned_by(player),
+            Target::Any => cell.get_top().is_some(),
+        }
+    }
+
+    fn get_moves_for(&self, board: &Board, player: Player) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for pattern in &self.document.moves {
+            match *pattern {
+                MovePattern::Place { kind, must_capture } => {
+                    for (row, col, cell) in board.cells() {
+                        if !cell.is_empty()
+                            || must_capture
+                                && self.find_captures(board, (row, col), player).is_empty()
+                        {
+                            continue;
+                        }
+                        moves.push(Move::Play {
+                            from: None,
+                            to: (row, col),
+                            piece: Self::placed_piece(kind),
+                            path: Vec::new(),
+                        });
+                    }
+                }
+                MovePattern::Drop { kind } => {
+                    for col in 0..board.get_num_cols() {
+                        let Some(row) = board
+                            .column(col)
+                            .take_while(|cell| cell.is_empty())
+                            .count()
+                            .checked_sub(1)
+                        else {
+                            continue;
+                        };
+                        moves.push(Move::Play {
+                            from: None,
+                            to: (row, col),
+                            piece: Self::placed_piece(kind),
+                            path: Vec::new(),
+                        });
+                    }
+                }
+                MovePattern::Slide {
+                    piece,
+                    directions,
+                    max_distance,
+                    capture,
+                } => {
+                    for (row, col, cell) in board.cells() {
+                        if !cell.is_owned_by(player)
+                            || !Self::piece_matches(piece, cell.get_top().unwrap())
+                        {
+                            continue;
+                        }
+                        for &direction in directions.get_steps() {
+                            let reach = max_distance.unwrap_or(usize::MAX);
+                            for (r, c, target) in board.ray(row, col, direction).take(reach) {
+                                let takes = capture && is_opponent(target, player);
+                                if target.is_empty() || takes {
+                                    moves.push(Move::step((row, col), (r, c)));
+                                }
+                                if !target.is_empty() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                MovePattern::Jump {
+                    piece,
+                    directions,
+                    over,
+                    chain,
+                    ..
+                } => {
+                    for (row, col, cell) in board.cells() {
+                        if !cell.is_owned_by(player)
+                            || !Self::piece_matches(piece, cell.get_top().unwrap())
+                        {
+                            continue;
+                        }
+                        let mut jumps = JumpChain {
+                            board,
+                            player,
+                            steps: directions.get_steps(),
+                            over,
+                            chain,
+                            from: (row, col),
+                            landings: Vec::new(),
+                            jumped: Vec::new(),
+                            moves: &mut moves,
+                        };
+                        jumps.extend((row, col));
+                    }
+                }
+            }
+        }
+        // Patterns can overlap, e.g. a step that is also a one-cell slide
+        let mut seen = HashSet::new();
+        moves.retain(|game_move| seen.insert(game_move.clone()));
+        moves
+    }
+
+    fn count_pieces(board: &Board, player: Player) -> usize {
+        board
+            .cells()
+            .filter(|(_, _, cell)| cell.is_owned_by(player))
+            .count()
+    }
+
+    // Whether the player's pieces join the two edges, searching outwards from the first one
+    fn connects(
+        board: &Board,
+        player: Player,
+        steps: &[(i32, i32)],
+        is_start: impl Fn(usize, usize) -> bool,
+        is_end: impl Fn(usize, usize) -> bool,
+    ) -> bool {
+        let mut queue: VecDeque<(usize, usize)> = board
+            .cells()
+            .filter(|&(row, col, cell)| is_start(row, col) && cell.is_owned_by(player))
+            .map(|(row, col, _)| (row, col))
+            .collect();
+        let mut visited: HashSet<(usize, usize)> = queue.iter().copied().collect();
+        while let Some((row, col)) = queue.pop_front() {
+            if is_end(row, col) {
+                return true;
+            }
+            for next in board.neighbors(row, col, steps) {
+                if board[next].is_owned_by(player) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        false
+    }
+
+    // Wins that don't wait for the game to end
+    fn has_won(&self, game: &Game, player: Player) -> bool {
+        let board = game.get_board();
+        let last_row = board.get_num_rows() - 1;
+        let last_col = board.get_num_cols() - 1;
+        self.document.win.iter().any(|condition| match *condition {
+            WinCondition::InARow { length } => board.lines(length).any(|line| {
+                line.iter()
+                    .all(|&position| board[position].is_owned_by(player))
+            }),
+            WinCondition::PieceCount { count } => Self::count_pieces(board, player) >= count,
+            WinCondition::Connection { sides, directions } => {
+                let steps = directions.get_steps();
+                let top_bottom = || {
+                    Self::connects(
+                        board,
+                        player,
+                        steps,
+                        |row, _| row == 0,
+                        |row, _| row == last_row,
+                    )
+                };
+                let left_right = || {
+                    Self::connects(
+                        board,
+                        player,
+                        steps,
+                        |_, col| col == 0,
+                        |_, col| col == last_col,
+                    )
+                };
+                match sides {
+                    Sides::TopBottom => top_bottom(),
+                    Sides::LeftRight => left_right(),
+                    Sides::Either => top_bottom() || left_right(),
+                }
+            }
+            WinCondition::Elimination => {
+                Self::count_pieces(board, player) > 0
+                    && (0..game.get_num_players())
+                        .map(Player)
+                        .filter(|&other| other != player)
+                        .all(|other| Self::count_pieces(board, other) == 0)
+            }
+            WinCondition::MostPieces | WinCondition::LastToMove => false,
+        })
+    }
+
+    // Play stops when nobody can move, or when the player to move can't and may not pass
+    fn is_stuck(&self, game: &Game) -> bool {
+        let board = game.get_board();
+        match self.document.pass {
+            PassPolicy::Never => self.get_moves_for(board, game.get_to_move()).is_empty(),
+            PassPolicy::WhenForced | PassPolicy::Always => (0..game.get_num_players())
+                .all(|player| self.get_moves_for(board, Player(player)).is_empty()),
+        }
+    }
+
+    fn compute_result(&self, game: &Game) -> GameResult {
+        let players = (0..game.get_num_players()).map(Player);
+        let winners: Vec<Player> = players
+            .clone()
+            .filter(|&player| self.has_won(game, player))
+            .collect();
+        if !winners.is_empty() {
+            return Some(winners);
+        }
+        let out_of_moves = self
+            .document
+            .max_moves
+            .is_some_and(|max_moves| game.get_move_num() >= max_moves);
+        let stuck = !out_of_moves && self.is_stuck(game);
+        if !out_of_moves && !stuck {
+            return None;
+        }
+        let has = |wanted: fn(&WinCondition) -> bool| self.document.win.iter().any(wanted);
+        if has(|condition| matches!(condition, WinCondition::MostPieces)) {
+            let counts: Vec<usize> = players
+                .clone()
+                .map(|player| Self::count_pieces(game.get_board(), player))
+                .collect();
+            let most = counts.iter().copied().max().unwrap_or(0);
+            // Nobody wins a game that ends with an empty board
+            return Some(
+                players
+                    .filter(|player| most > 0 && counts[player.to_usize()] == most)
+                    .collect(),
+            );
+        }
+        if stuck && has(|condition| matches!(condition, WinCondition::LastToMove)) {
+            let num_players = game.get_num_players();
+            let last = (game.get_to_move().to_usize() + num_players - 1) % num_players;
+            return Some(vec![Player(last)]);
+        }
+        Some(Vec::new())
+    }
+
+    fn get_result(&self, game: &Game) -> GameResult {
+        let key = (game.get_hash(), game.get_move_num());
+        if let Some((hash, move_num, result)) = &*self.last_result.borrow() {
+            if (*hash, *move_num) == key {
+                return result.clone();
+            }
+        }
+        let result = self.compute_result(game);
+        *self.last_result.borrow_mut() = Some((key.0, key.1, result.clone()));
+        result
+    }
+}
+
+impl GameSpec for RuleSpec {
+    // The setup can have pieces for every player the game allows, and only the pieces of
+    // players in this game are placed
+    fn get_initial_board(&self, _config: &GameConfig, num_players: usize) -> Board {
+        let shape = &self.document.board;
+        let mut board = Board::new(shape.rows, shape.cols);
+        for &position in &shape.blocked {
+            board[position] = BoardCell::Blocked;
+        }
+        let in_game =
+            |piece: &&InitialPiece| piece.player.is_none_or(|player| player < num_players);
+        for piece in self.document.setup.iter().filter(in_game) {
+            board[(piece.row, piece.col)] = BoardCell::Piece(Piece {
+                owner: piece.player.map(Player),
+                kind: piece.kind,
+            });
+        }
+        board
+    }
+
+    fn get_initial_to_move(&self) -> Player {
+        Player(0)
+    }
+
+    fn get_valid_moves(&self, game: &Game) -> Vec<Move> {
+        if self.get_result(game).is_some() {
+            return Vec::new();
+        }
+        self.get_moves_for(game.get_board(), game.get_to_move())
+    }
+
+    fn transition(&self, game: &Game, game_move: &Move) -> MoveDelta {
+        let Move::Play {
+            from,
+            to,
+            piece,
+            path,
+        } = game_move
+        else {
+            unreachable!("Passes are handled by Game");
+        };
+        let player = game.get_to_move();
+        let mut board = game.get_board().clone();
+        let mut delta = MoveDelta {
+            changed_cells: Vec::new(),
+            next_to_move: game.get_next_player(),
+        };
+        match from {
+            None => {
+                let placed = BoardCell::Piece(Piece {
+                    owner: Some(player),
+                    kind: piece.unwrap_or(0),
+                });
+                set_cell(&mut board, &mut delta, *to, placed);
+            }
+            Some(from) => {
+                let moving = board[*from].clone();
+                let moving_piece = moving.get_top().expect("Only pieces move");
+                set_cell(&mut board, &mut delta, *from, BoardCell::Empty);
+                // Jumps pass over a piece between each pair of landings; slides only over
+                // empty cells
+                let waypoints: Vec<(usize, usize)> = std::iter::once(*from)
+                    .chain(path.iter().copied())
+                    .chain(std::iter::once(*to))
+                    .collect();
+                for pair in waypoints.windows(2) {
+                    let (start, end) = (pair[0], pair[1]);
+                    let direction = (
+                        (end.0 as i32 - start.0 as i32).signum(),
+                        (end.1 as i32 - start.1 as i32).signum(),
+                    );
+                    let mut at = start;
+                    while let Some(next) = step(&board, at, direction).filter(|&next| next != end) {
+                        if self.captures_jumped(moving_piece, &board[next], player) {
+                            set_cell(&mut board, &mut delta, next, BoardCell::Empty);
+                        }
+                        at = next;
+                    }
+                }
+                set_cell(&mut board, &mut delta, *to, moving);
+            }
+        }
+        for (position, cell) in self.find_captures(&board, *to, player) {
+            set_cell(&mut board, &mut delta, position, cell);
+        }
+        delta
+    }
+
+    fn is_win(&self, game: &Game, player: Player) -> bool {
+        self.get_result(game) == Some(vec![player])
+    }
+
+    fn is_tie(&self, game: &Game) -> bool {
+        self.get_result(game) == Some(Vec::new())
+    }
+
+    // Players who win together share first place
+    fn get_outcome(&self, game: &Game) -> Option<Outcome> {
+        let winners = self.get_result(game)?;
+        if winners.len() <= 1 {
+            return None;
+        }
+        let placements = (0..game.get_num_players())
+            .map(|player| usize::from(!winners.contains(&Player(player))))
+            .collect();
+        Some(Outcome::Placements(placements))
+    }
+
+    fn get_pass_policy(&self) -> PassPolicy {
+        self.document.pass
+    }
+
+    // The same position can be over or not depending on how many moves are left
+    fn hash_includes_move_num(&self) -> bool {
+        self.document.max_moves.is_some()
+    }
+
+    fn get_metadata(&self, _config: &GameConfig) -> GameMetadata {
+        let document = &self.document;
+        let move_style = if document
+            .moves
+            .iter()
+            .any(|pattern| matches!(pattern, MovePattern::Drop { .. }))
+        {
+            MoveStyle::Gravity
+        } else if document.moves.iter().all(MovePattern::is_placement) {
+            MoveStyle::Placement
+        } else {
+            MoveStyle::Movement
+        };
+        GameMetadata {
+            name: document.name.clone(),
+            rules: document.rules.clone(),
+            min_players: document.min_players,
+            max_players: document.max_players,
+            player_styles: document.player_styles.clone(),
+            coordinate_style: document.coordinate_style,
+            move_style,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_elements::GameStatus;
+
+    fn new_game(json: &str, num_players: usize) -> Game {
+        let spec = RuleSpec::from_json(json).unwrap();
+        Game::new(Box::new(spec), num_players, &GameConfig::default()).unwrap()
+    }
+
+    fn play(game: &mut Game, moves: &[Move]) {
+        for game_move in moves {
+            game.make_move(game_move).unwrap();
+        }
+    }
+
+    #[test]
+    fn plays_an_interpreted_game_of_tic_tac_toe() {
+        let mut game = new_game(
+            r#"{
+                "name": "Tic-tac-toe",
+                "board": { "rows": 3, "cols": 3 },
+                "moves": [{ "type": "place" }],
+                "win": [{ "type": "in_a_row", "length": 3 }]
+            }"#,
+            2,
+        );
+        assert_eq!(game.get_valid_moves().len(), 9);
+        play(
+            &mut game,
+            &[
+                Move::place(1, 1),
+                Move::place(0, 0),
+                Move::place(0, 2),
+                Move::place(2, 0),
+            ],
+        );
+        assert_eq!(game.get_valid_moves().len(), 5);
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        play(&mut game, &[Move::place(1, 0), Move::place(2, 2)]);
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        play(&mut game, &[Move::place(1, 2)]);
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(0)));
+        assert!(game.get_valid_moves().is_empty());
+    }
+
+    #[test]
+    fn nothing_moves_onto_or_through_blocked_cells() {
+        let game = new_game(
+            r#"{
+                "name": "Walled tic-tac-toe",
+                "board": { "rows": 3, "cols": 3, "blocked": [[1, 1]] },
+                "moves": [{ "type": "place" }],
+                "win": [{ "type": "in_a_row", "length": 3 }]
+            }"#,
+            2,
+        );
+        assert_eq!(game.get_board()[(1, 1)], BoardCell::Blocked);
+        assert_eq!(game.get_valid_moves().len(), 8);
+        assert!(!game.get_valid_moves().contains(&Move::place(1, 1)));
+
+        let game = new_game(
+            r#"{
+                "name": "Walled slide",
+                "board": { "rows": 1, "cols": 3, "blocked": [[0, 1]] },
+                "setup": [{ "row": 0, "col": 0, "player": 0 }, { "row": 0, "col": 2, "player": 1 }],
+                "moves": [{ "type": "slide", "capture": true }],
+                "win": [{ "type": "elimination" }]
+            }"#,
+            2,
+        );
+        // The wall keeps the pieces apart, so there is nothing to move
+        assert!(game.get_valid_moves().is_empty());
+    }
+
+    #[test]
+    fn flanked_pieces_change_owner() {
+        let mut game = new_game(
+            r#"{
+                "name": "Line Othello",
+                "board": { "rows": 1, "cols": 4 },
+                "setup": [{ "row": 0, "col": 1, "player": 0 }, { "row": 0, "col": 2, "player": 1 }],
+                "moves": [{ "type": "place", "must_capture": true }],
+                "captures": [{ "type": "flank" }],
+                "win": [{ "type": "most_pieces" }]
+            }"#,
+            2,
+        );
+        assert_eq!(game.get_valid_moves(), vec![Move::place(0, 3)]);
+        play(&mut game, &[Move::place(0, 3)]);
+        assert!(game.get_board()[(0, 2)].is_owned_by(Player(0)));
+        // Player 1 has nothing left to flank, so the game is over
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(0)));
+    }
+
+    #[test]
+    fn only_pieces_of_players_in_the_game_are_set_up() {
+        let json = r#"{
+            "name": "Three-way chase",
+            "min_players": 2,
+            "max_players": 3,
+            "board": { "rows": 3, "cols": 3 },
+            "setup": [
+                { "row": 0, "col": 0, "player": 0 },
+                { "row": 2, "col": 2, "player": 1 },
+                { "row": 0, "col": 2, "player": 2 },
+                { "row": 1, "col": 1 }
+            ],
+            "moves": [{ "type": "slide", "max_distance": 1, "capture": true }],
+            "win": [{ "type": "elimination" }]
+        }"#;
+        let pieces = |game: &Game| {
+            game.get_board()
+                .cells()
+                .filter(|(_, _, cell)| !cell.is_empty())
+                .count()
+        };
+
+        let two_players = new_game(json, 2);
+        assert!(two_players.get_board()[(0, 2)].is_empty());
+        assert_eq!(pieces(&two_players), 3);
+        assert_eq!(two_players.get_game_status(), GameStatus::Ongoing);
+
+        let mut three_players = new_game(json, 3);
+        assert!(three_players.get_board()[(0, 2)].is_owned_by(Player(2)));
+        assert_eq!(pieces(&three_players), 4);
+        // Every player gets a turn
+        for player in 0..3 {
+            assert_eq!(three_players.get_to_move(), Player(player));
+            let game_move = three_players.get_valid_moves()[0].clone();
+            play(&mut three_players, &[game_move]);
+        }
+    }
+
+    #[test]
+    fn move_limits_make_the_move_number_part_of_the_hash() {
+        let hash_after_passes = |max_moves: &str| {
+            let mut game = new_game(
+                &format!(
+                    r#"{{
+                        "name": "Patient tic-tac-toe",
+                        "board": {{ "rows": 3, "cols": 3 }},
+                        "moves": [{{ "type": "place" }}],
+                        "win": [{{ "type": "in_a_row", "length": 3 }}],
+                        "pass": "always",
+                        "max_moves": {}
+                    }}"#,
+                    max_moves
+                ),
+                2,
+            );
+            let initial_hash = game.get_hash();
+            play(&mut game, &[Move::Pass, Move::Pass]);
+            (initial_hash, game.get_hash())
+        };
+        // Both players passing leads back to the same board with the same player to move
+        let (initial_hash, hash) = hash_after_passes("null");
+        assert_eq!(hash, initial_hash);
+        let (initial_hash, hash) = hash_after_passes("4");
+        assert_ne!(hash, initial_hash);
+    }
+
+    #[test]
+    fn chained_jumps_take_every_piece_jumped() {
+        let mut game = new_game(
+            r#"{
+                "name": "Diagonal checkers",
+                "board": { "rows": 5, "cols": 5 },
+                "setup": [
+                    { "row": 4, "col": 0, "player": 0 },
+                    { "row": 3, "col": 1, "player": 1 },
+                    { "row": 1, "col": 3, "player": 1 }
+                ],
+                "moves": [{ "type": "jump", "directions": "diagonal", "capture": true, "chain": true }],
+                "win": [{ "type": "elimination" }]
+            }"#,
+            2,
+        );
+        // The chain can't stop halfway
+        let chained_jump = Move::Play {
+            from: Some((4, 0)),
+            to: (0, 4),
+            piece: None,
+            path: vec![(2, 2)],
+        };
+        assert_eq!(game.get_valid_moves(), vec![chained_jump.clone()]);
+        play(&mut game, &[chained_jump]);
+        let board = game.get_board();
+        assert!(board[(0, 4)].is_owned_by(Player(0)));
+        for position in [(4, 0), (3, 1), (2, 2), (1, 3)] {
+            assert!(board[position].is_empty());
+        }
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(0)));
+    }
+
+    #[test]
+    fn drops_land_above_blocked_and_occupied_cells() {
+        let mut game = new_game(
+            r#"{
+                "name": "Caves",
+                "board": { "rows": 4, "cols": 3, "blocked": [[2, 0], [0, 2]] },
+                "setup": [{ "row": 3, "col": 1, "player": 1 }],
+                "moves": [{ "type": "drop" }],
+                "win": [{ "type": "in_a_row", "length": 3 }]
+            }"#,
+            2,
+        );
+        // Nothing fits into the column blocked at the top
+        assert_eq!(
+            game.get_valid_moves(),
+            vec![Move::place(1, 0), Move::place(2, 1)]
+        );
+        play(&mut game, &[Move::place(1, 0), Move::place(2, 1)]);
+        let board = game.get_board();
+        assert!(board[(1, 0)].is_owned_by(Player(0)));
+        assert!(board[(2, 1)].is_owned_by(Player(1)));
+        assert_eq!(board[(2, 0)], BoardCell::Blocked);
+        assert_eq!(
+            game.get_valid_moves(),
+            vec![Move::place(0, 0), Move::place(1, 1)]
+        );
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn custodian_captures_remove_a_single_enclosed_piece() {
+        let mut game = new_game(
+            r#"{
+                "name": "Enclosure",
+                "board": { "rows": 1, "cols": 5 },
+                "setup": [
+                    { "row": 0, "col": 0, "player": 0 },
+                    { "row": 0, "col": 1, "player": 1 },
+                    { "row": 0, "col": 3, "player": 1 }
+                ],
+                "moves": [{ "type": "place" }],
+                "captures": [{ "type": "custodian" }],
+                "win": [{ "type": "in_a_row", "length": 3 }]
+            }"#,
+            2,
+        );
+        play(&mut game, &[Move::place(0, 2)]);
+        let board = game.get_board();
+        assert!(board[(0, 1)].is_empty());
+        // Nothing of player 0's is on the far side of this one
+        assert!(board[(0, 3)].is_owned_by(Player(1)));
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn connecting_the_edges_wins() {
+        let mut game = new_game(
+            r#"{
+                "name": "Tiny Hex",
+                "board": { "rows": 3, "cols": 3 },
+                "moves": [{ "type": "place" }],
+                "win": [{ "type": "connection", "sides": "top_bottom", "directions": "orthogonal" }]
+            }"#,
+            2,
+        );
+        play(
+            &mut game,
+            &[
+                Move::place(0, 1),
+                Move::place(0, 0),
+                Move::place(1, 2),
+                Move::place(1, 0),
+            ],
+        );
+        // Diagonal steps don't join the chain
+        play(&mut game, &[Move::place(2, 2)]);
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        play(&mut game, &[Move::place(2, 1), Move::place(1, 1)]);
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(0)));
+    }
+
+    #[test]
+    fn taking_the_last_opposing_piece_wins_by_elimination() {
+        let mut game = new_game(
+            r#"{
+                "name": "Skirmish",
+                "board": { "rows": 2, "cols": 2 },
+                "setup": [
+                    { "row": 0, "col": 0, "player": 0 },
+                    { "row": 0, "col": 1, "player": 1 },
+                    { "row": 1, "col": 1, "player": 1 }
+                ],
+                "moves": [{ "type": "slide", "directions": "orthogonal", "max_distance": 1, "capture": true }],
+                "win": [{ "type": "elimination" }]
+            }"#,
+            2,
+        );
+        play(&mut game, &[Move::step((0, 0), (0, 1))]);
+        assert!(game.get_board()[(0, 1)].is_owned_by(Player(0)));
+        // Player 1 still has a piece
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        play(&mut game, &[Move::step((1, 1), (0, 1))]);
+        assert!(game.get_board()[(1, 1)].is_empty());
+        assert_eq!(game.get_game_status(), GameStatus::Win(Player(1)));
+    }
+}
@@ -0,0 +1,452 @@
+// A game described as data rather than code. A rule document is written in JSON and played by
+// RuleSpec, so a new game needs no new build. Every enum below is written in snake_case, and
+// tagged enums name their variant in a "type" field, e.g. { "type": "in_a_row", "length": 3 }.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+use crate::board::{DIRECTIONS, ORTHOGONAL_DIRECTIONS};
+use crate::game_elements::{PieceKind, MAX_PLAYERS};
+use crate::game_spec::PassPolicy;
+use crate::metadata::{CoordinateStyle, PlayerStyle};
+
+// Limit on either side of the board, so that documents can't ask for huge boards
+pub const MAX_BOARD_SIZE: usize = 32;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RuleDocument {
+    pub name: String,
+    // A short summary of the rules for the players
+    #[serde(default)]
+    pub rules: String,
+    #[serde(default = "default_min_players")]
+    pub min_players: usize,
+    #[serde(default = "default_max_players")]
+    pub max_players: usize,
+    pub board: BoardShape,
+    // Pieces on the board before the first move
+    #[serde(default)]
+    pub setup: Vec<InitialPiece>,
+    // Every way a player can move; the player to move may use any of them
+    pub moves: Vec<MovePattern>,
+    // Applied around the destination after every move
+    #[serde(default)]
+    pub captures: Vec<CaptureRule>,
+    // A player who meets any of these wins
+    pub win: Vec<WinCondition>,
+    #[serde(default)]
+    pub pass: PassPolicy,
+    // The game ends after this many moves, passes included
+    #[serde(default)]
+    pub max_moves: Option<usize>,
+    #[serde(default)]
+    pub player_styles: Vec<PlayerStyle>,
+    #[serde(default)]
+    pub coordinate_style: CoordinateStyle,
+}
+
+fn default_min_players() -> usize {
+    2
+}
+
+fn default_max_players() -> usize {
+    2
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BoardShape {
+    pub rows: usize,
+    pub cols: usize,
+    // Cells that are not part of the playing area, as [row, col]
+    #[serde(default)]
+    pub blocked: Vec<(usize, usize)>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct InitialPiece {
+    pub row: usize,
+    pub col: usize,
+    // Left out for neutral pieces
+    #[serde(default)]
+    pub player: Option<usize>,
+    #[serde(default)]
+    pub kind: PieceKind,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Directions {
+    Orthogonal,
+    Diagonal,
+    #[default]
+    All,
+}
+
+// Whose pieces a jump may go over
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    #[default]
+    Opponent,
+    Own,
+    Any,
+}
+
+// Which opposite edges a connection has to join
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Sides {
+    TopBottom,
+    LeftRight,
+    #[default]
+    Either,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MovePattern {
+    // A new piece on any empty cell
+    Place {
+        #[serde(default)]
+        kind: PieceKind,
+        // Only where one of the capture rules takes something, as in Othello
+        #[serde(default)]
+        must_capture: bool,
+    },
+    // A new piece dropped into a column, falling until it lands on something
+    Drop {
+        #[serde(default)]
+        kind: PieceKind,
+    },
+    // One of the player's pieces moving in a straight line over empty cells
+    Slide {
+        // The kind of piece that moves this way; any kind when left out
+        #[serde(default)]
+        piece: Option<PieceKind>,
+        #[serde(default)]
+        directions: Directions,
+        // 1 for single steps; unlimited when left out
+        #[serde(default)]
+        max_distance: Option<usize>,
+        // May also end on an opponent's piece, taking it
+        #[serde(default)]
+        capture: bool,
+    },
+    // One of the player's pieces jumping over an adjacent piece to the empty cell behind it
+    Jump {
+        #[serde(default)]
+        piece: Option<PieceKind>,
+        #[serde(default)]
+        directions: Directions,
+        #[serde(default)]
+        over: Target,
+        // Removes the pieces jumped over
+        #[serde(default)]
+        capture: bool,
+        // Keeps jumping while it can, as in checkers
+        #[serde(default)]
+        chain: bool,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CaptureRule {
+    // Lines of opponent pieces between the moved piece and another of the player's pieces
+    // change owner, as in Othello
+    Flank {
+        #[serde(default)]
+        directions: Directions,
+    },
+    // A single opponent piece between the moved piece and another of the player's pieces is
+    // removed, as in Hnefatafl
+    Custodian {
+        #[serde(default)]
+        directions: Directions,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WinCondition {
+    // This many of the player's pieces in a row, column or diagonal
+    InARow {
+        length: usize,
+    },
+    // At least this many of the player's pieces on the board
+    PieceCount {
+        count: usize,
+    },
+    // A chain of the player's pieces joining opposite edges, as in Hex
+    Connection {
+        #[serde(default)]
+        sides: Sides,
+        #[serde(default)]
+        directions: Directions,
+    },
+    // The player is the only one with pieces left
+    Elimination,
+    // Once the game ends, the players with the most pieces share the win
+    MostPieces,
+    // Once the player to move is stuck, the player who moved before them wins
+    LastToMove,
+}
+
+impl Directions {
+    pub fn get_steps(self) -> &'static [(i32, i32)] {
+        match self {
+            Directions::Orthogonal => &ORTHOGONAL_DIRECTIONS,
+            Directions::Diagonal => &DIAGONAL_DIRECTIONS,
+            Directions::All => &DIRECTIONS,
+        }
+    }
+}
+
+const DIAGONAL_DIRECTIONS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+impl MovePattern {
+    // Whether the pattern brings new pieces onto the board rather than moving them
+    pub fn is_placement(&self) -> bool {
+        matches!(self, MovePattern::Place { .. } | MovePattern::Drop { .. })
+    }
+}
+
+impl RuleDocument {
+    pub fn from_json(json: &str) -> Result<RuleDocument, String> {
+        let document: RuleDocument = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    // Checks everything serde can't, so that a document that loads can always be played
+    pub fn validate(&self) -> Result<(), String> {
+        let board = &self.board;
+        if !(1..=MAX_BOARD_SIZE).contains(&board.rows)
+            || !(1..=MAX_BOARD_SIZE).contains(&board.cols)
+        {
+            return Err(format!(
+                "The board must have between 1 and {} rows and columns",
+                MAX_BOARD_SIZE
+            ));
+        }
+        if self.min_players == 0
+            || self.min_players > self.max_players
+            || self.max_players > MAX_PLAYERS
+        {
+            return Err(format!(
+                "The players must be a range within 1 to {}",
+                MAX_PLAYERS
+            ));
+        }
+        if self.player_styles.len() > MAX_PLAYERS {
+            return Err(format!(
+                "There can be at most {} player styles",
+                MAX_PLAYERS
+            ));
+        }
+
+        let in_bounds = |row: usize, col: usize| row < board.rows && col < board.cols;
+        let mut blocked = HashSet::new();
+        for &(row, col) in &board.blocked {
+            if !in_bounds(row, col) {
+                return Err(format!("Blocked cell ({}, {}) is off the board", row, col));
+            }
+            if !blocked.insert((row, col)) {
+                return Err(format!("Blocked cell ({}, {}) is listed twice", row, col));
+            }
+        }
+        let mut occupied = HashSet::new();
+        for piece in &self.setup {
+            let (row, col) = (piece.row, piece.col);
+            if !in_bounds(row, col) {
+                return Err(format!(
+                    "Setup piece at ({}, {}) is off the board",
+                    row, col
+                ));
+            }
+            if blocked.contains(&(row, col)) {
+                return Err(format!(
+                    "Setup piece at ({}, {}) is on a blocked cell",
+                    row, col
+                ));
+            }
+            if !occupied.insert((row, col)) {
+                return Err(format!("Two setup pieces are at ({}, {})", row, col));
+            }
+            // Pieces of players who aren't in a particular game are left off its board
+            if let Some(player) = piece.player.filter(|&player| player >= self.max_players) {
+                return Err(format!(
+                    "Setup piece at ({}, {}) belongs to player {}, but the game has at most {} players",
+                    row, col, player, self.max_players
+                ));
+            }
+        }
+
+        if self.moves.is_empty() {
+            return Err("There must be at least one move pattern".to_string());
+        }
+        if self.win.is_empty() {
+            return Err("There must be at least one win condition".to_string());
+        }
+        for pattern in &self.moves {
+            if let MovePattern::Slide {
+                max_distance: Some(0),
+                ..
+            } = pattern
+            {
+                return Err("A slide must be able to move at least one cell".to_string());
+            }
+        }
+        for condition in &self.win {
+            match condition {
+                WinCondition::InARow { length }
+                    if *length < 2 || *length > board.rows.max(board.cols) =>
+                {
+                    return Err(format!(
+                        "In-a-row length {} doesn't fit on the board",
+                        length
+                    ));
+                }
+                WinCondition::PieceCount { count: 0 } => {
+                    return Err("A piece count win needs at least one piece".to_string());
+                }
+                WinCondition::LastToMove if self.pass != PassPolicy::Never => {
+                    return Err("Last-to-move wins need a game without passes".to_string());
+                }
+                _ => {}
+            }
+        }
+
+        // Players without a way to bring pieces in need some from the start, or they could
+        // never move or be eliminated the moment the game begins
+        let places = self.moves.iter().any(MovePattern::is_placement);
+        let eliminates = self
+            .win
+            .iter()
+            .any(|condition| matches!(condition, WinCondition::Elimination));
+        if !places || eliminates {
+            for player in 0..self.max_players {
+                if !self.setup.iter().any(|piece| piece.player == Some(player)) {
+                    return Err(format!("Player {} has no setup pieces", player));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIC_TAC_TOE: &str = r#"{
+        "name": "Tic-tac-toe",
+        "board": { "rows": 3, "cols": 3 },
+        "moves": [{ "type": "place" }],
+        "win": [{ "type": "in_a_row", "length": 3 }]
+    }"#;
+
+    // A document with one field replaced or added
+    fn with(field: &str, value: &str) -> String {
+        let mut document: serde_json::Value = serde_json::from_str(TIC_TAC_TOE).unwrap();
+        document[field] = serde_json::from_str(value).unwrap();
+        document.to_string()
+    }
+
+    fn error_of(json: &str) -> String {
+        RuleDocument::from_json(json).expect_err("the document should be rejected")
+    }
+
+    #[test]
+    fn accepts_a_valid_document() {
+        let document = RuleDocument::from_json(TIC_TAC_TOE).unwrap();
+        assert_eq!(document.min_players, 2);
+        assert_eq!(document.pass, PassPolicy::Never);
+    }
+
+    #[test]
+    fn rejects_documents_serde_cant_read() {
+        assert!(error_of("{").contains("EOF"));
+        assert!(error_of(&with("colour", "\"red\"")).contains("unknown field"));
+        assert!(error_of(&with("moves", r#"[{ "type": "teleport" }]"#)).contains("teleport"));
+    }
+
+    #[test]
+    fn rejects_boards_and_player_counts_out_of_range() {
+        assert!(error_of(&with("board", r#"{ "rows": 0, "cols": 3 }"#)).contains("rows"));
+        assert!(error_of(&with("board", r#"{ "rows": 3, "cols": 33 }"#)).contains("rows"));
+        assert!(error_of(&with("min_players", "3")).contains("players"));
+        assert!(error_of(&with("max_players", "9")).contains("players"));
+    }
+
+    #[test]
+    fn rejects_misplaced_cells_and_pieces() {
+        let blocked = |cells: &str| {
+            with(
+                "board",
+                &format!(r#"{{ "rows": 3, "cols": 3, "blocked": {} }}"#, cells),
+            )
+        };
+        assert!(error_of(&blocked("[[3, 0]]")).contains("off the board"));
+        assert!(error_of(&blocked("[[1, 1], [1, 1]]")).contains("twice"));
+
+        assert!(
+            error_of(&with("setup", r#"[{ "row": 0, "col": 5, "player": 0 }]"#))
+                .contains("off the board")
+        );
+        assert!(error_of(&with(
+            "setup",
+            r#"[{ "row": 0, "col": 0, "player": 0 }, { "row": 0, "col": 0, "player": 1 }]"#
+        ))
+        .contains("Two setup pieces"));
+        assert!(
+            error_of(&with("setup", r#"[{ "row": 0, "col": 0, "player": 2 }]"#))
+                .contains("at most 2 players")
+        );
+    }
+
+    #[test]
+    fn rejects_unplayable_rules() {
+        assert!(error_of(&with("moves", "[]")).contains("move pattern"));
+        assert!(error_of(&with("win", "[]")).contains("win condition"));
+        assert!(error_of(&with(
+            "moves",
+            r#"[{ "type": "slide", "max_distance": 0 }]"#
+        ))
+        .contains("at least one cell"));
+        assert!(
+            error_of(&with("win", r#"[{ "type": "in_a_row", "length": 4 }]"#))
+                .contains("doesn't fit")
+        );
+        assert!(
+            error_of(&with("win", r#"[{ "type": "piece_count", "count": 0 }]"#))
+                .contains("at least one piece")
+        );
+        let last_to_move = with("win", r#"[{ "type": "last_to_move" }]"#);
+        let mut document: serde_json::Value = serde_json::from_str(&last_to_move).unwrap();
+        document["pass"] = "always".into();
+        assert!(error_of(&document.to_string()).contains("without passes"));
+    }
+
+    #[test]
+    fn movement_games_need_setup_pieces_for_every_player() {
+        let mut document: serde_json::Value =
+            serde_json::from_str(&with("moves", r#"[{ "type": "slide" }]"#)).unwrap();
+        document["max_players"] = 3.into();
+        document["setup"] = serde_json::from_str(
+            r#"[{ "row": 0, "col": 0, "player": 0 }, { "row": 2, "col": 2, "player": 1 }]"#,
+        )
+        .unwrap();
+        assert!(error_of(&document.to_string()).contains("Player 2 has no setup pieces"));
+
+        document["setup"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::from_str(r#"{ "row": 0, "col": 2, "player": 2 }"#).unwrap());
+        assert!(RuleDocument::from_json(&document.to_string()).is_ok());
+    }
+}
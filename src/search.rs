@@ -0,0 +1,197 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::alphabeta::{BestReplySearcher, ParanoidSearcher};
+use crate::clock::now_ms;
+use crate::game::Game;
+use crate::game_elements::Move;
+use crate::mcts::MctsSearcher;
+use crate::opt::MaxnSearcher;
+use crate::transposition::TranspositionTable;
+
+// Reading the clock is comparatively slow, so only do it every this many nodes
+const TIME_CHECK_INTERVAL: usize = 256;
+
+#[derive(PartialEq, Copy, Clone)]
+#[wasm_bindgen]
+pub enum SearchAlgorithm {
+    Minimax,
+    MonteCarloTreeSearch,
+    Paranoid,
+    BestReply,
+}
+
+pub struct SearchLimits {
+    pub time_budget_ms: Option<f64>,
+    pub node_budget: Option<usize>,
+    // Plies searched by the depth-first searches, and how deep MCTS grows its tree
+    pub max_depth: usize,
+    // Whether max^n tries the most promising moves first; off, it keeps the spec's order
+    pub move_ordering: bool,
+}
+
+impl SearchLimits {
+    pub fn has_budget(&self) -> bool {
+        self.time_budget_ms.is_some() || self.node_budget.is_some()
+    }
+}
+
+// Counters collected while searching, on top of the nodes counted by the SearchBudget
+#[derive(Default)]
+pub struct SearchStats {
+    pub leaf_evaluations: usize,
+    pub prunes: usize,
+    pub duplicates_skipped: usize,
+    pub max_depth_reached: usize,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    // Score vector at the end of the principal variation, one entry per player
+    pub score: Vec<f64>,
+    pub principal_variation: Vec<Move>,
+    // Depth of the deepest completed iteration
+    pub depth: usize,
+    // Deepest ply visited, including iterations that were cut short
+    pub max_depth_reached: usize,
+    pub nodes: usize,
+    pub leaf_evaluations: usize,
+    pub prunes: usize,
+    pub duplicates_skipped: usize,
+    pub elapsed_ms: f64,
+}
+
+impl SearchResult {
+    pub fn new(
+        principal_variation: Vec<Move>,
+        score: Vec<f64>,
+        depth: usize,
+        nodes: usize,
+        stats: &SearchStats,
+        start_ms: f64,
+    ) -> Self {
+        SearchResult {
+            best_move: principal_variation.first().cloned(),
+            score,
+            principal_variation,
+            depth,
+            max_depth_reached: stats.max_depth_reached,
+            nodes,
+            leaf_evaluations: stats.leaf_evaluations,
+            prunes: stats.prunes,
+            duplicates_skipped: stats.duplicates_skipped,
+            elapsed_ms: now_ms() - start_ms,
+        }
+    }
+}
+
+pub trait Searcher {
+    // Positions at the depth cutoff are scored by the game's evaluation function
+    fn search(&mut self, game: &mut Game, limits: &SearchLimits) -> SearchResult;
+}
+
+pub fn create_searcher(
+    algorithm: SearchAlgorithm,
+    tt: &Rc<RefCell<TranspositionTable>>,
+) -> Box<dyn Searcher> {
+    match algorithm {
+        SearchAlgorithm::Minimax => Box::new(MaxnSearcher::new(Rc::clone(tt))),
+        SearchAlgorithm::MonteCarloTreeSearch => Box::new(MctsSearcher),
+        SearchAlgorithm::Paranoid => Box::new(ParanoidSearcher),
+        SearchAlgorithm::BestReply => Box::new(BestReplySearcher),
+    }
+}
+
+// Tracks the nodes and time used by a depth-first search against its limits
+pub struct SearchBudget {
+    nodes: usize,
+    node_budget: Option<usize>,
+    deadline_ms: Option<f64>,
+    // Budgets are only enforced once a move is guaranteed to be found
+    pub abortable: bool,
+    aborted: bool,
+}
+
+impl SearchBudget {
+    pub fn new(limits: &SearchLimits) -> Self {
+        SearchBudget {
+            nodes: 0,
+            node_budget: limits.node_budget,
+            deadline_ms: limits.time_budget_ms.map(|budget| now_ms() + budget),
+            abortable: false,
+            aborted: false,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        SearchBudget {
+            nodes: 0,
+            node_budget: None,
+            deadline_ms: None,
+            abortable: false,
+            aborted: false,
+        }
+    }
+
+    // Counts a visited node and returns whether the search has to stop
+    pub fn visit_node(&mut self) -> bool {
+        self.nodes += 1;
+        if self.abortable && !self.aborted {
+            self.aborted = self.node_budget.is_some_and(|budget| self.nodes >= budget)
+                || (self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+                    && self
+                        .deadline_ms
+                        .is_some_and(|deadline| now_ms() >= deadline));
+        }
+        self.aborted
+    }
+
+    pub fn get_nodes(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+}
+
+// Positions and limits shared by the tests of the searchers
+#[cfg(test)]
+pub mod test_support {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::eval::HashEvaluationFunction;
+    use crate::push_up_four::PushUpFourSpec;
+
+    // Push Up Four positions to search from, as indices into the valid moves played in turn
+    pub const OPENINGS: [&[usize]; 3] = [&[], &[3, 3, 2], &[0, 6, 1, 5]];
+
+    pub fn new_game(num_players: usize, opening: &[usize]) -> Game {
+        let mut game = Game::new(
+            Box::new(PushUpFourSpec),
+            num_players,
+            &GameConfig::default(),
+        )
+        .unwrap();
+        game.set_evaluation_function(Some(Box::new(HashEvaluationFunction)));
+        for &col in opening {
+            let game_move = game.get_valid_moves()[col].clone();
+            game.transition(&game_move);
+        }
+        game
+    }
+
+    // A budget that is never used up, so every iteration up to max_depth completes
+    pub fn unlimited_limits(max_depth: usize, move_ordering: bool) -> SearchLimits {
+        SearchLimits {
+            time_budget_ms: None,
+            node_budget: Some(usize::MAX),
+            max_depth,
+            move_ordering,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::mem::size_of;
+
+use crate::game_elements::Move;
+
+pub struct TTEntry {
+    pub key: u64,
+    // Remaining search depth below the stored node
+    pub depth: usize,
+    pub score: Vec<f64>,
+    pub best_move: Option<Move>,
+    // False when pruning with alphas from outside the subtree made the score a bound
+    pub exact: bool,
+    generation: u8,
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<TTEntry>>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    pub fn new(memory_budget_bytes: usize, num_players: usize) -> Self {
+        // Each entry also owns a heap allocated score vector
+        let entry_size = size_of::<Option<TTEntry>>() + num_players * size_of::<f64>();
+        let max_entries = (memory_budget_bytes / entry_size).max(1);
+
+        // Round down to a power of two so that indexing is a mask
+        let num_entries = 1 << (usize::BITS - 1 - max_entries.leading_zeros());
+        TranspositionTable {
+            entries: (0..num_entries).map(|_| None).collect(),
+            generation: 0,
+        }
+    }
+
+    // Entries from previous searches are kept for lookups but are replaced first
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&TTEntry> {
+        self.entries[self.index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: usize,
+        score: Vec<f64>,
+        best_move: Option<Move>,
+        exact: bool,
+    ) {
+        let index = self.index(key);
+        let generation = self.generation;
+
+        // Replace empty slots, entries from older searches, or results that searched less deeply
+        let replace = match &self.entries[index] {
+            None => true,
+            Some(entry) => entry.generation != generation || depth >= entry.depth,
+        };
+        if replace {
+            self.entries[index] = Some(TTEntry {
+                key,
+                depth,
+                score,
+                best_move,
+                exact,
+                generation,
+            });
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+}
@@ -0,0 +1,148 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::GameState;
+use crate::game_elements::{BoardCell, Piece, Player};
+
+// Fixed seed so that hashes are reproducible between runs
+const ZOBRIST_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// Stands in for the contents of a blocked cell, which no piece encoding can produce
+const BLOCKED_CODE: u64 = u64::MAX;
+
+pub struct ZobristKeys {
+    num_cols: usize,
+    // Indexed by row * num_cols + col
+    cell_keys: Vec<u64>,
+    to_move_keys: Vec<u64>,
+    // Mixed with the move number when positions at different points of the game must differ
+    move_num_key: u64,
+    includes_move_num: bool,
+}
+
+// The splitmix64 finalizer, which spreads any change in the input over the whole output
+pub fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+// Packs a piece and its height in the stack into one number; neutral pieces get owner 0
+fn piece_code(level: usize, piece: &Piece) -> u64 {
+    let owner = piece.owner.map_or(0, |player| player.to_usize() as u64 + 1);
+    ((level as u64) << 48) | (owner << 40) | piece.kind as u64
+}
+
+impl ZobristKeys {
+    pub fn new(num_rows: usize, num_cols: usize, num_players: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
+        ZobristKeys {
+            num_cols,
+            cell_keys: (0..num_rows * num_cols).map(|_| rng.gen()).collect(),
+            to_move_keys: (0..num_players).map(|_| rng.gen()).collect(),
+            move_num_key: rng.gen(),
+            includes_move_num: false,
+        }
+    }
+
+    // For games whose outcome depends on the move number as well as the position, e.g. ones
+    // that end after a fixed number of moves
+    pub fn with_move_num(mut self) -> Self {
+        self.includes_move_num = true;
+        self
+    }
+
+    // Cells can hold any number of piece kinds and stacks of any height, so rather than
+    // tabulating a key for every possible cell, the contents are mixed into a random key
+    // for the position. Empty cells contribute nothing.
+    pub fn cell_key(&self, row: usize, col: usize, cell: &BoardCell) -> u64 {
+        let position_key = self.cell_keys[row * self.num_cols + col];
+        match cell {
+            BoardCell::Empty => 0,
+            BoardCell::Blocked => mix(position_key ^ BLOCKED_CODE),
+            _ => cell
+                .get_pieces()
+                .iter()
+                .enumerate()
+                .fold(position_key, |key, (level, piece)| {
+                    mix(key ^ piece_code(level, piece))
+                }),
+        }
+    }
+
+    pub fn to_move_key(&self, player: Player) -> u64 {
+        self.to_move_keys[player.to_usize()]
+    }
+
+    // 0 unless the keys were created with_move_num
+    pub fn move_num_key(&self, move_num: usize) -> u64 {
+        if !self.includes_move_num {
+            return 0;
+        }
+        mix(self.move_num_key ^ move_num as u64)
+    }
+
+    // Hashes a state from scratch; only needed for the initial state, as Game updates the hash
+    // with the cells each move changes. Unless the keys are with_move_num, move_num is
+    // excluded so that transpositions hash identically.
+    pub fn hash_state(&self, state: &GameState) -> u64 {
+        let mut hash =
+            self.to_move_key(state.get_to_move()) ^ self.move_num_key(state.get_move_num());
+        for (row, col, cell) in state.get_board().cells() {
+            hash ^= self.cell_key(row, col, cell);
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(owner: Option<usize>, kind: usize) -> Piece {
+        Piece {
+            owner: owner.map(Player),
+            kind,
+        }
+    }
+
+    #[test]
+    fn every_kind_of_cell_hashes_differently() {
+        let keys = ZobristKeys::new(2, 2, 2);
+        let cells = [
+            BoardCell::Empty,
+            BoardCell::Blocked,
+            BoardCell::owned_by(Player(0)),
+            BoardCell::owned_by(Player(1)),
+            BoardCell::Piece(piece(Some(0), 1)),
+            BoardCell::Piece(piece(None, 0)),
+            BoardCell::from_pieces(vec![piece(Some(0), 0), piece(Some(1), 0)]),
+            // The same pieces stacked the other way round
+            BoardCell::from_pieces(vec![piece(Some(1), 0), piece(Some(0), 0)]),
+            BoardCell::from_pieces(vec![piece(Some(0), 0), piece(Some(0), 0)]),
+        ];
+        let cell_keys: Vec<u64> = cells.iter().map(|cell| keys.cell_key(1, 0, cell)).collect();
+        for (i, key) in cell_keys.iter().enumerate() {
+            for other_key in &cell_keys[i + 1..] {
+                assert_ne!(key, other_key);
+            }
+        }
+        // Keys depend on the position as well as the contents
+        assert_ne!(
+            keys.cell_key(0, 1, &BoardCell::Blocked),
+            keys.cell_key(1, 0, &BoardCell::Blocked)
+        );
+    }
+
+    #[test]
+    fn a_stack_hashes_differently_from_its_top_piece() {
+        let keys = ZobristKeys::new(1, 1, 2);
+        let top = piece(Some(1), 0);
+        let stack = BoardCell::from_pieces(vec![piece(Some(0), 0), top]);
+        assert_ne!(
+            keys.cell_key(0, 0, &stack),
+            keys.cell_key(0, 0, &BoardCell::Piece(top))
+        );
+    }
+}